@@ -0,0 +1,87 @@
+//! Micro-benchmark of the `VM::run` dispatch loop.
+//!
+//! Runs a long straight-line chunk of arithmetic, comparison and global
+//! variable opcodes and reports how many instructions per second the VM
+//! executes. The one-time `Chunk::verify` pass that `VM::interpret` performs
+//! is timed on its own and subtracted, since for straight-line code it costs
+//! as much as executing the chunk. Run with `cargo bench --bench dispatch`.
+
+use kentauri::bytecode::chunk::Chunk;
+use kentauri::bytecode::instruction::Instructions;
+use kentauri::bytecode::opcode::OpCode;
+use kentauri::value::value::Value;
+use kentauri::vm::vm::VM;
+use std::time::{Duration, Instant};
+
+const BLOCKS: usize = 20_000;
+const ROUNDS: usize = 200;
+
+fn build_chunk() -> Chunk {
+    let mut chunk = Chunk::new();
+    let one = chunk.add_const(Value::Number(1.0)) as u8;
+    let two = chunk.add_const(Value::Number(2.0)) as u8;
+    let name = chunk.add_const(Value::from("acc")) as u8;
+
+    chunk.write_code(OpCode::OP_CONST, 1);
+    chunk.write_byte(one, 1);
+    chunk.write_code(OpCode::OP_DEF_GLOBAL, 1);
+    chunk.write_byte(name, 1);
+
+    for _ in 0..BLOCKS {
+        chunk.write_code(OpCode::OP_GET_GLOBAL, 2);
+        chunk.write_byte(name, 2);
+        chunk.write_code(OpCode::OP_CONST, 2);
+        chunk.write_byte(two, 2);
+        chunk.write_code(OpCode::OP_MUL, 2);
+        chunk.write_code(OpCode::OP_CONST, 2);
+        chunk.write_byte(one, 2);
+        chunk.write_code(OpCode::OP_SUB, 2);
+        chunk.write_code(OpCode::OP_SET_GLOBAL, 2);
+        chunk.write_byte(name, 2);
+        chunk.write_code(OpCode::OP_CONST, 2);
+        chunk.write_byte(two, 2);
+        chunk.write_code(OpCode::OP_LESS, 2);
+        chunk.write_code(OpCode::OP_NOT, 2);
+        chunk.write_code(OpCode::OP_POP, 2);
+    }
+
+    chunk.write_code(OpCode::OP_RETURN, 3);
+
+    chunk
+}
+
+fn main() {
+    let mut chunk = build_chunk();
+    let instructions = Instructions::new(&chunk.code).count();
+    let mut vm = VM::new();
+    let mut total = Duration::new(0, 0);
+    let mut verification = Duration::new(0, 0);
+
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        chunk.verify().expect("benchmark chunk is valid");
+        verification += start.elapsed();
+
+        let start = Instant::now();
+        chunk = match vm.interpret(chunk) {
+            Ok(chunk) => chunk,
+            Err(e) => panic!("benchmark chunk failed: {}", e),
+        };
+        total += start.elapsed();
+    }
+
+    let executed = (instructions * ROUNDS) as f64;
+    let dispatch = total.checked_sub(verification).unwrap_or(total);
+    println!(
+        "interpret: {} instructions in {:.3}s, {:.1} M instructions/s",
+        instructions * ROUNDS,
+        total.as_secs_f64(),
+        executed / total.as_secs_f64() / 1_000_000.0
+    );
+    println!(
+        "verify:    {:.3}s, dispatch: {:.3}s, {:.1} M instructions/s",
+        verification.as_secs_f64(),
+        dispatch.as_secs_f64(),
+        executed / dispatch.as_secs_f64() / 1_000_000.0
+    );
+}
@@ -0,0 +1,163 @@
+//! Benchmarks the scripts under `benches/scripts`, each compiled once per optimisation
+//! level and interpreted `ROUNDS` times. Reports the wall time, the instructions the VM
+//! executed and the heap allocations made while interpreting, then the time it takes to
+//! compile each script in a single pass and through a syntax tree. Run with
+//! `cargo bench --bench scripts`, optionally followed by names to select scripts.
+//!
+//! The language has no functions, loops or classes yet, so the workloads are unrolled
+//! straight-line code; method dispatch, property access and binary-trees are missing.
+
+use kentauri::compiler::compiler::Compiler;
+use kentauri::compiler::peephole::OptLevel;
+use kentauri::output::sink::OutputBuffer;
+use kentauri::vm::vm::VM;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+const ROUNDS: usize = 2_000;
+
+const LEVELS: [OptLevel; 3] = [
+    OptLevel::None,
+    OptLevel::Peephole,
+    OptLevel::Superinstructions,
+];
+
+// Large enough to never run out, the fuel left tells how many instructions ran.
+const FUEL: u64 = u64::MAX;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+struct Measurement {
+    time: Duration,
+    instructions: u64,
+    allocations: usize,
+    allocated: usize,
+}
+
+fn scripts(filters: &[String]) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("benches/scripts");
+    let mut scripts: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "kt"))
+        .filter(|path| {
+            let name = path.file_stem().unwrap().to_string_lossy();
+            filters.is_empty() || filters.iter().any(|filter| name.contains(filter.as_str()))
+        })
+        .collect();
+    scripts.sort();
+    scripts
+}
+
+fn measure(source: &str, level: OptLevel) -> Measurement {
+    let mut compiler = Compiler::new();
+    compiler.set_opt_level(level);
+    let mut chunk = compiler.compile(source).expect("benchmark script compiles");
+
+    let mut vm = VM::new();
+    let output = OutputBuffer::new();
+    vm.set_output(output.sink());
+    vm.set_fuel(Some(FUEL));
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated = ALLOCATED.load(Ordering::Relaxed);
+    let start = Instant::now();
+
+    for _ in 0..ROUNDS {
+        chunk = match vm.interpret(chunk) {
+            Ok(chunk) => chunk,
+            Err(e) => panic!("benchmark script failed: {}", e),
+        };
+        output.take();
+    }
+
+    Measurement {
+        time: start.elapsed(),
+        instructions: FUEL - vm.remaining_fuel().unwrap(),
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        allocated: ALLOCATED.load(Ordering::Relaxed) - allocated,
+    }
+}
+
+fn measure_compile(source: &str, single_pass: bool) -> Duration {
+    let mut compiler = Compiler::new();
+    compiler.set_single_pass(single_pass);
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        compiler.compile(source).expect("benchmark script compiles");
+    }
+
+    start.elapsed()
+}
+
+fn main() {
+    let filters: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with('-'))
+        .collect();
+
+    println!(
+        "{:<20} {:>10} {:>14} {:>10} {:>12} {:>12}",
+        "script", "time", "instructions", "M instr/s", "allocations", "bytes"
+    );
+    for path in scripts(&filters) {
+        let source = fs::read_to_string(&path).unwrap();
+
+        for level in LEVELS.iter() {
+            let m = measure(&source, *level);
+            println!(
+                "{:<20} {:>8.3}ms {:>14} {:>10.1} {:>12} {:>12}",
+                format!(
+                    "{} -O{}",
+                    path.file_stem().unwrap().to_string_lossy(),
+                    *level as u8
+                ),
+                m.time.as_secs_f64() * 1000.0,
+                m.instructions,
+                m.instructions as f64 / m.time.as_secs_f64() / 1_000_000.0,
+                m.allocations,
+                m.allocated
+            );
+        }
+    }
+
+    println!();
+    println!("{:<20} {:>12} {:>12}", "compile", "single-pass", "tree");
+    for path in scripts(&filters) {
+        let source = fs::read_to_string(&path).unwrap();
+        println!(
+            "{:<20} {:>10.3}ms {:>10.3}ms",
+            path.file_stem().unwrap().to_string_lossy(),
+            measure_compile(&source, true).as_secs_f64() * 1000.0,
+            measure_compile(&source, false).as_secs_f64() * 1000.0
+        );
+    }
+}
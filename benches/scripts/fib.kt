@@ -0,0 +1,74 @@
+// Iterative fibonacci, one step per line since there are no loops.
+var a = 0;
+var b = 1;
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+{ var t = a + b; a = b; b = t; }
+print b;
@@ -0,0 +1,106 @@
+// Sums 1..100 with a local and a global accumulator, unrolled.
+var sum = 0;
+{
+    var i = 0;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+    i = i + 1; sum = sum + i;
+}
+print sum;
@@ -0,0 +1,76 @@
+// Builds a string by repeated concatenation and repetition.
+var s = "";
+var piece = "ab";
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+s = s + piece;
+piece = piece * 2;
+s = s + piece * 3;
+piece = piece * 2;
+s = s + piece * 3;
+piece = piece * 2;
+s = s + piece * 3;
+piece = piece * 2;
+s = s + piece * 3;
+piece = piece * 2;
+s = s + piece * 3;
+piece = piece * 2;
+s = s + piece * 3;
+print s == "";
@@ -0,0 +1,37 @@
+// Mixed globals, locals, arithmetic, comparisons and printing, the closest
+// the language gets to the zoo benchmark without classes.
+var cat = 1;
+var dog = 2;
+var name = "zoo";
+var count = 0;
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+{ var lion = cat + dog; var bear = lion * 2 - cat; count = count + bear / lion; cat = dog; dog = lion; name = name + "!"; print count > 100 == !(cat < dog); }
+print count;
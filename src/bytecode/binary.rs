@@ -0,0 +1,325 @@
+//! Versioned binary serialization of compiled chunks (`.kbc` files).
+//!
+//! Layout, all integers little endian:
+//!
+//! ```text
+//! magic     4 bytes  "KBC\0"
+//! version   u16
+//! checksum  u32      FNV-1a of the payload
+//! payload:
+//!   code      u32 length, bytes
+//!   lines     u32 length, u32 byte count per source line
+//!   constants u32 length, each a u8 tag followed by its data:
+//!             0 nil, 1 bool (u8), 2 number (f64 bits), 3 string (u32 length, UTF-8)
+//!   locals    u32 length, each a name (u32 length, UTF-8), then u32 slot, start and end
+//! ```
+
+use crate::bytecode::chunk::{Chunk, LocalInfo};
+use crate::bytecode::verifier::VerifyError;
+use crate::util::byte_utils::fnv1a_32;
+use crate::value::value::{Value, ValuePool};
+use std::convert::TryInto;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+pub const MAGIC: &[u8; 4] = b"KBC\0";
+pub const VERSION: u16 = 2;
+
+const HEADER_LEN: usize = 10;
+
+const TAG_NIL: u8 = 0;
+const TAG_BOOL: u8 = 1;
+const TAG_NUMBER: u8 = 2;
+const TAG_STRING: u8 = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadError {
+    BadMagic,
+    UnsupportedVersion(u16),
+    ChecksumMismatch { expected: u32, actual: u32 },
+    Truncated,
+    TrailingBytes,
+    UnknownConstantTag(u8),
+    InvalidString,
+    LineTableMismatch,
+    LocalOutOfCode,
+    UnserializableConstant(String),
+    Invalid(VerifyError),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            LoadError::BadMagic => write!(f, "Not a kentauri bytecode file"),
+            LoadError::UnsupportedVersion(v) => {
+                write!(
+                    f,
+                    "Unsupported bytecode version {} (expected {})",
+                    v, VERSION
+                )
+            }
+            LoadError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "Checksum mismatch: expected {:#010x}, found {:#010x}",
+                expected, actual
+            ),
+            LoadError::Truncated => write!(f, "Unexpected end of bytecode file"),
+            LoadError::TrailingBytes => write!(f, "Unexpected data after chunk"),
+            LoadError::UnknownConstantTag(tag) => write!(f, "Unknown constant tag {}", tag),
+            LoadError::InvalidString => write!(f, "String constant is not valid UTF-8"),
+            LoadError::LineTableMismatch => {
+                write!(f, "Line table does not cover the code")
+            }
+            LoadError::LocalOutOfCode => write!(f, "Local variable outside of the code"),
+            LoadError::UnserializableConstant(c) => {
+                write!(f, "Constant {} cannot be serialized", c)
+            }
+            LoadError::Invalid(e) => write!(f, "Invalid bytecode: {}", e),
+        }
+    }
+}
+
+pub fn is_bytecode(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+pub fn serialize(chunk: &Chunk) -> Result<Vec<u8>, LoadError> {
+    let mut payload = Vec::new();
+
+    write_u32(&mut payload, chunk.code.len());
+    payload.extend_from_slice(&chunk.code);
+
+    write_u32(&mut payload, chunk.line_code_index().len());
+    for count in chunk.line_code_index() {
+        write_u32(&mut payload, *count);
+    }
+
+    write_u32(&mut payload, chunk.const_pool.values.len());
+    for value in chunk.const_pool.values.iter() {
+        match value {
+            Value::Nil => payload.push(TAG_NIL),
+            Value::Bool(b) => {
+                payload.push(TAG_BOOL);
+                payload.push(*b as u8);
+            }
+            Value::Number(n) => {
+                payload.push(TAG_NUMBER);
+                payload.extend_from_slice(&n.to_bits().to_le_bytes());
+            }
+            Value::String(s) => {
+                payload.push(TAG_STRING);
+                let string = s.string.as_str();
+                write_u32(&mut payload, string.len());
+                payload.extend_from_slice(string.as_bytes());
+            }
+            Value::Object(_) => {
+                return Err(LoadError::UnserializableConstant(value.to_string()));
+            }
+        }
+    }
+
+    write_u32(&mut payload, chunk.locals.len());
+    for local in chunk.locals.iter() {
+        write_u32(&mut payload, local.name.len());
+        payload.extend_from_slice(local.name.as_bytes());
+        write_u32(&mut payload, local.slot);
+        write_u32(&mut payload, local.start);
+        write_u32(&mut payload, local.end);
+    }
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&fnv1a_32(&payload).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+
+    Ok(bytes)
+}
+
+/// Loads a serialized chunk, checking its header and checksum and verifying the decoded
+/// chunk before handing it out.
+pub fn deserialize(bytes: &[u8]) -> Result<Chunk, LoadError> {
+    if !is_bytecode(bytes) {
+        return Err(LoadError::BadMagic);
+    }
+    if bytes.len() < HEADER_LEN {
+        return Err(LoadError::Truncated);
+    }
+
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if version != VERSION {
+        return Err(LoadError::UnsupportedVersion(version));
+    }
+
+    let expected = u32::from_le_bytes(bytes[6..10].try_into().unwrap());
+    let payload = &bytes[HEADER_LEN..];
+    let actual = fnv1a_32(payload);
+    if expected != actual {
+        return Err(LoadError::ChecksumMismatch { expected, actual });
+    }
+
+    let mut reader = Reader {
+        bytes: payload,
+        pos: 0,
+    };
+
+    let code_len = reader.read_u32()?;
+    let code = reader.read_bytes(code_len)?.to_vec();
+
+    let line_count = reader.read_u32()?;
+    let mut lines = Vec::with_capacity(line_count.min(payload.len()));
+    for _ in 0..line_count {
+        lines.push(reader.read_u32()?);
+    }
+    if lines.iter().sum::<usize>() != code.len() {
+        return Err(LoadError::LineTableMismatch);
+    }
+
+    let const_count = reader.read_u32()?;
+    let mut const_pool = ValuePool::new();
+    for _ in 0..const_count {
+        let value = match reader.read_u8()? {
+            TAG_NIL => Value::Nil,
+            TAG_BOOL => Value::Bool(reader.read_u8()? != 0),
+            TAG_NUMBER => {
+                let bits = reader.read_bytes(8)?.try_into().unwrap();
+                Value::Number(f64::from_bits(u64::from_le_bytes(bits)))
+            }
+            TAG_STRING => Value::from(reader.read_string()?),
+            tag => return Err(LoadError::UnknownConstantTag(tag)),
+        };
+        const_pool.values.push(value);
+    }
+
+    let local_count = reader.read_u32()?;
+    let mut locals = Vec::with_capacity(local_count.min(payload.len()));
+    for _ in 0..local_count {
+        let local = LocalInfo {
+            name: reader.read_string()?.to_string(),
+            slot: reader.read_u32()?,
+            start: reader.read_u32()?,
+            end: reader.read_u32()?,
+        };
+        if local.start > local.end || local.end > code.len() {
+            return Err(LoadError::LocalOutOfCode);
+        }
+        locals.push(local);
+    }
+
+    if reader.pos != payload.len() {
+        return Err(LoadError::TrailingBytes);
+    }
+
+    let mut chunk = Chunk::from_parts(code, const_pool, lines);
+    chunk.locals = locals;
+    chunk.verify().map_err(LoadError::Invalid)?;
+
+    Ok(chunk)
+}
+
+fn write_u32(bytes: &mut Vec<u8>, value: usize) {
+    bytes.extend_from_slice(&(value as u32).to_le_bytes());
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], LoadError> {
+        let end = self.pos.checked_add(len).ok_or(LoadError::Truncated)?;
+        let bytes = self.bytes.get(self.pos..end).ok_or(LoadError::Truncated)?;
+        self.pos = end;
+
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, LoadError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<usize, LoadError> {
+        let bytes = self.read_bytes(4)?.try_into().unwrap();
+
+        Ok(u32::from_le_bytes(bytes) as usize)
+    }
+
+    fn read_string(&mut self) -> Result<&'a str, LoadError> {
+        let len = self.read_u32()?;
+        std::str::from_utf8(self.read_bytes(len)?).map_err(|_| LoadError::InvalidString)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deserialize, serialize, LoadError, HEADER_LEN};
+    use crate::bytecode::chunk::{Chunk, LocalInfo};
+    use crate::bytecode::opcode::OpCode;
+    use crate::value::value::Value;
+
+    fn sample() -> Chunk {
+        let mut chunk = Chunk::new();
+        let number = chunk.add_const(Value::Number(2.5)) as u8;
+        let string = chunk.add_const(Value::from("name")) as u8;
+        chunk.add_const(Value::Bool(true));
+        chunk.add_const(Value::Nil);
+
+        chunk.write_code(OpCode::OP_CONST, 1);
+        chunk.write_byte(number, 1);
+        chunk.write_code(OpCode::OP_DEF_GLOBAL, 2);
+        chunk.write_byte(string, 2);
+        chunk.write_code(OpCode::OP_RETURN, 4);
+        chunk.locals.push(LocalInfo {
+            name: String::from("l\u{f6}cal"),
+            slot: 0,
+            start: 2,
+            end: 4,
+        });
+
+        chunk
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let chunk = sample();
+        let loaded = deserialize(&serialize(&chunk).unwrap()).unwrap();
+
+        assert_eq!(chunk.code, loaded.code);
+        assert_eq!(chunk.line_code_index(), loaded.line_code_index());
+        assert_eq!(4, loaded.const_pool.values.len());
+        assert!(loaded.const_pool.values[0].eq(&Value::Number(2.5)));
+        assert!(loaded.const_pool.values[1].eq(&Value::from("name")));
+        assert!(loaded.const_pool.values[2].eq(&Value::Bool(true)));
+        assert!(loaded.const_pool.values[3].eq(&Value::Nil));
+        assert_eq!(4, loaded.get_code_line(4));
+        assert_eq!(chunk.locals, loaded.locals);
+    }
+
+    #[test]
+    fn test_rejects_corrupted_files() {
+        let bytes = serialize(&sample()).unwrap();
+
+        assert_eq!(Err(LoadError::BadMagic), deserialize(b"var a;").map(|_| ()));
+
+        let mut version = bytes.clone();
+        version[4] = 9;
+        assert_eq!(
+            Err(LoadError::UnsupportedVersion(9)),
+            deserialize(&version).map(|_| ())
+        );
+
+        let mut flipped = bytes.clone();
+        flipped[HEADER_LEN + 4] ^= 0xff;
+        match deserialize(&flipped) {
+            Err(LoadError::ChecksumMismatch { .. }) => (),
+            _ => panic!("corruption not detected"),
+        }
+
+        let truncated = &bytes[..bytes.len() - 3];
+        match deserialize(truncated) {
+            Err(LoadError::ChecksumMismatch { .. }) => (),
+            _ => panic!("truncation not detected"),
+        }
+    }
+}
@@ -1,10 +1,25 @@
 use crate::bytecode::opcode::OpCode;
+use crate::bytecode::verifier;
+use crate::bytecode::verifier::VerifyError;
 use crate::value::value::{Value, ValuePool};
+use std::iter;
+
+/// Where a local variable lives, for debuggers: its value is in stack slot `slot` while
+/// the code from offset `start` up to `end` runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalInfo {
+    pub name: String,
+    pub slot: usize,
+    pub start: usize,
+    pub end: usize,
+}
 
 pub struct Chunk {
     pub code: Vec<u8>,
     pub const_pool: ValuePool,
     line_code_index: Vec<usize>,
+    /// The locals of the code, in the order they were declared.
+    pub locals: Vec<LocalInfo>,
 }
 
 impl Chunk {
@@ -13,9 +28,25 @@ impl Chunk {
             code: Vec::new(),
             const_pool: ValuePool::new(),
             line_code_index: Vec::new(),
+            locals: Vec::new(),
         }
     }
 
+    /// Reassembles a chunk from its serialized parts, see `bytecode::binary`.
+    pub fn from_parts(code: Vec<u8>, const_pool: ValuePool, line_code_index: Vec<usize>) -> Self {
+        Chunk {
+            code,
+            const_pool,
+            line_code_index,
+            locals: Vec::new(),
+        }
+    }
+
+    /// Number of code bytes emitted for each source line, indexed by line.
+    pub fn line_code_index(&self) -> &[usize] {
+        &self.line_code_index
+    }
+
     pub fn write_code(&mut self, code: OpCode, line: usize) {
         self.write_byte(code as u8, line);
     }
@@ -29,7 +60,7 @@ impl Chunk {
             let start = self.line_code_index.len();
             let end = line;
 
-            for i in start..end {
+            for _ in start..end {
                 self.line_code_index.push(0);
             }
 
@@ -56,20 +87,58 @@ impl Chunk {
         pos
     }
 
+    /// Drops the code from offset `len` on, along with its entries in the line table.
+    pub fn truncate(&mut self, len: usize) {
+        let mut excess = self.code.len().saturating_sub(len);
+        self.code.truncate(len);
+
+        for count in self.line_code_index.iter_mut().rev() {
+            if excess == 0 {
+                break;
+            }
+            let removed = excess.min(*count);
+            *count -= removed;
+            excess -= removed;
+        }
+    }
+
     pub fn get_byte_sequence(&self, start: usize, last: usize) -> &[u8] {
         &self.code[start..=last]
     }
 
     pub fn get_code_line(&self, offset: usize) -> usize {
-        let mut line = 0;
-        let mut current_offset = 0;
+        let mut covered = 0;
 
-        while line != self.line_code_index.len() - 1 && offset > current_offset {
-            line += 1;
-            current_offset += self.line_code_index[line];
+        for (line, count) in self.line_code_index.iter().enumerate() {
+            covered += count;
+            if offset < covered {
+                return line;
+            }
         }
 
-        line
+        self.line_code_index.len().saturating_sub(1)
+    }
+
+    /// The line of every code offset, for lookups faster than `get_code_line`.
+    pub fn code_lines(&self) -> Vec<usize> {
+        self.line_code_index
+            .iter()
+            .enumerate()
+            .flat_map(|(line, count)| iter::repeat_n(line, *count))
+            .collect()
+    }
+
+    /// The locals that hold a value when the instruction at `offset` is about to run.
+    pub fn live_locals(&self, offset: usize) -> impl Iterator<Item = &LocalInfo> {
+        self.locals
+            .iter()
+            .filter(move |local| local.start <= offset && offset < local.end)
+    }
+
+    /// Checks that the chunk is well-formed, see `verifier::verify`. Returns the maximum
+    /// stack depth reached by the code.
+    pub fn verify(&self) -> Result<usize, VerifyError> {
+        verifier::verify(self)
     }
 }
 
@@ -77,6 +146,7 @@ impl Chunk {
 mod tests {
     use super::Chunk;
     use crate::bytecode::opcode::OpCode;
+    use crate::value::value::Value;
 
     #[test]
     fn test_line_resolution() {
@@ -87,4 +157,18 @@ mod tests {
         assert_eq!(0, chunk.get_code_line(0));
         assert_eq!(3, chunk.get_code_line(1));
     }
+
+    #[test]
+    fn test_verify() {
+        let mut chunk = Chunk::new();
+        chunk.write_code(OpCode::OP_CONST, 0);
+        chunk.write_byte(0, 0);
+        assert!(chunk.verify().is_err());
+
+        chunk.add_const(Value::Nil);
+        assert!(chunk.verify().is_err());
+
+        chunk.write_code(OpCode::OP_RETURN, 0);
+        assert!(chunk.verify().is_ok());
+    }
 }
@@ -0,0 +1,133 @@
+use crate::bytecode::opcode::OpCode;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Instruction {
+    pub offset: usize,
+    pub op: OpCode,
+    pub operand: Option<usize>,
+    /// Operand of the superinstructions that take two.
+    pub second_operand: Option<usize>,
+}
+
+impl Instruction {
+    pub fn width(&self) -> usize {
+        1 + self.op.operand_len()
+    }
+
+    pub fn next_offset(&self) -> usize {
+        self.offset + self.width()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DecodeError {
+    UnknownOpcode { offset: usize, byte: u8 },
+    MissingOperand { offset: usize, op: OpCode },
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            DecodeError::UnknownOpcode { offset, byte } => {
+                write!(f, "Unknown opcode {} at offset {}", byte, offset)
+            }
+            DecodeError::MissingOperand { offset, op } => {
+                write!(f, "Missing operand of {:?} at offset {}", op, offset)
+            }
+        }
+    }
+}
+
+pub fn decode(code: &[u8], offset: usize) -> Result<Instruction, DecodeError> {
+    let byte = code[offset];
+    let op = OpCode::try_from(byte).map_err(|_| DecodeError::UnknownOpcode { offset, byte })?;
+
+    // Multi-byte operands are little endian.
+    let mut operands = [None, None];
+    let mut start = offset + 1;
+    for (operand, len) in operands.iter_mut().zip(op.operand_widths()) {
+        match code.get(start..start + len) {
+            Some(bytes) => *operand = Some(bytes.iter().rev().fold(0, |n, b| n << 8 | *b as usize)),
+            None => return Err(DecodeError::MissingOperand { offset, op }),
+        }
+        start += len;
+    }
+
+    Ok(Instruction {
+        offset,
+        op,
+        operand: operands[0],
+        second_operand: operands[1],
+    })
+}
+
+/// Iterates over the instructions of a code stream, stopping after the first undecodable one.
+pub struct Instructions<'a> {
+    code: &'a [u8],
+    offset: usize,
+    failed: bool,
+}
+
+impl<'a> Instructions<'a> {
+    pub fn new(code: &'a [u8]) -> Self {
+        Instructions {
+            code,
+            offset: 0,
+            failed: false,
+        }
+    }
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = Result<Instruction, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.offset >= self.code.len() {
+            return None;
+        }
+
+        let result = decode(self.code, self.offset);
+        match &result {
+            Ok(instruction) => self.offset = instruction.next_offset(),
+            Err(_) => self.failed = true,
+        }
+
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecodeError, Instructions};
+    use crate::bytecode::opcode::OpCode;
+
+    #[test]
+    fn test_decode_stream() {
+        let code = [
+            OpCode::OP_CONST as u8,
+            0,
+            OpCode::OP_CONST_LONG as u8,
+            0x01,
+            0x02,
+            0x03,
+            OpCode::OP_RETURN as u8,
+            200,
+        ];
+        let decoded: Vec<_> = Instructions::new(&code).collect();
+
+        assert_eq!(4, decoded.len());
+        assert_eq!(Some(0), decoded[0].unwrap().operand);
+        assert_eq!(Some(0x030201), decoded[1].unwrap().operand);
+        assert_eq!(OpCode::OP_RETURN, decoded[2].unwrap().op);
+        assert_eq!(
+            Err(DecodeError::UnknownOpcode {
+                offset: 7,
+                byte: 200
+            }),
+            decoded[3]
+        );
+    }
+}
@@ -1,3 +1,5 @@
+pub mod binary;
 pub mod chunk;
 pub mod instruction;
 pub mod opcode;
+pub mod verifier;
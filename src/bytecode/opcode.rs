@@ -1,7 +1,9 @@
 use num_enum::TryFromPrimitive;
+use std::convert::TryFrom;
 
 #[repr(u8)]
-#[derive(Debug, TryFromPrimitive)]
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, TryFromPrimitive)]
 pub enum OpCode {
     OP_RETURN,
     OP_CONST,
@@ -23,5 +25,90 @@ pub enum OpCode {
     OP_GET_GLOBAL,
     OP_SET_GLOBAL,
     OP_GET_LOCAL,
-    OP_SET_LOCAL
+    OP_SET_LOCAL,
+    OP_ECHO,
+    OP_CONST_LONG,
+    OP_NOT_EQUAL,
+    OP_GREATER_EQUAL,
+    OP_LESS_EQUAL,
+    /// Adds the constant of the first operand to the local of the second.
+    OP_ADD_CONST_LOCAL,
+    /// Pushes the locals of both operands.
+    OP_GET_LOCALS,
+}
+
+impl OpCode {
+    /// Converts a byte into an opcode without a range check.
+    ///
+    /// # Safety
+    ///
+    /// `byte` must be the discriminant of an `OpCode` variant, as is the case for every opcode
+    /// position of a chunk that passed verification.
+    #[inline]
+    pub unsafe fn from_verified(byte: u8) -> Self {
+        debug_assert!(OpCode::try_from(byte).is_ok());
+        std::mem::transmute::<u8, OpCode>(byte)
+    }
+
+    /// Number of operand bytes that follow the opcode in the code stream.
+    pub fn operand_len(self) -> usize {
+        self.operand_widths().iter().sum()
+    }
+
+    /// Width in bytes of each operand, in the order they follow the opcode.
+    pub fn operand_widths(self) -> &'static [usize] {
+        match self {
+            OpCode::OP_CONST
+            | OpCode::OP_DEF_GLOBAL
+            | OpCode::OP_GET_GLOBAL
+            | OpCode::OP_SET_GLOBAL
+            | OpCode::OP_GET_LOCAL
+            | OpCode::OP_SET_LOCAL => &[1],
+            OpCode::OP_CONST_LONG => &[3],
+            OpCode::OP_ADD_CONST_LOCAL | OpCode::OP_GET_LOCALS => &[1, 1],
+            _ => &[],
+        }
+    }
+
+    /// Whether the first operand of this opcode is an index into the constant pool.
+    pub fn has_const_operand(self) -> bool {
+        matches!(
+            self,
+            OpCode::OP_CONST
+                | OpCode::OP_CONST_LONG
+                | OpCode::OP_DEF_GLOBAL
+                | OpCode::OP_GET_GLOBAL
+                | OpCode::OP_SET_GLOBAL
+                | OpCode::OP_ADD_CONST_LOCAL
+        )
+    }
+
+    /// Number of values the instruction pops from and pushes onto the stack.
+    pub fn stack_effect(self) -> (usize, usize) {
+        match self {
+            OpCode::OP_RETURN | OpCode::OP_ADD_CONST_LOCAL => (0, 0),
+            OpCode::OP_GET_LOCALS => (0, 2),
+            OpCode::OP_CONST
+            | OpCode::OP_CONST_LONG
+            | OpCode::OP_NIL
+            | OpCode::OP_TRUE
+            | OpCode::OP_FALSE
+            | OpCode::OP_GET_GLOBAL
+            | OpCode::OP_GET_LOCAL => (0, 1),
+            OpCode::OP_NEGATE | OpCode::OP_NOT | OpCode::OP_SET_GLOBAL | OpCode::OP_SET_LOCAL => {
+                (1, 1)
+            }
+            OpCode::OP_EQUAL
+            | OpCode::OP_GREATER
+            | OpCode::OP_LESS
+            | OpCode::OP_NOT_EQUAL
+            | OpCode::OP_GREATER_EQUAL
+            | OpCode::OP_LESS_EQUAL
+            | OpCode::OP_ADD
+            | OpCode::OP_SUB
+            | OpCode::OP_MUL
+            | OpCode::OP_DIV => (2, 1),
+            OpCode::OP_PRINT | OpCode::OP_ECHO | OpCode::OP_POP | OpCode::OP_DEF_GLOBAL => (1, 0),
+        }
+    }
 }
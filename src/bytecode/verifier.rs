@@ -0,0 +1,283 @@
+use crate::bytecode::chunk::Chunk;
+use crate::bytecode::instruction::{decode, DecodeError};
+use crate::bytecode::opcode::OpCode;
+use crate::value::value::Value;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+    Decode(DecodeError),
+    ConstantOutOfBounds {
+        offset: usize,
+        index: usize,
+    },
+    InvalidGlobalName {
+        offset: usize,
+        index: usize,
+    },
+    StackUnderflow {
+        offset: usize,
+        op: OpCode,
+    },
+    LocalOutOfRange {
+        offset: usize,
+        slot: usize,
+        depth: usize,
+    },
+    MissingReturn,
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            VerifyError::Decode(e) => write!(f, "{}", e),
+            VerifyError::ConstantOutOfBounds { offset, index } => write!(
+                f,
+                "Constant index {} out of bounds at offset {}",
+                index, offset
+            ),
+            VerifyError::InvalidGlobalName { offset, index } => write!(
+                f,
+                "Constant {} used as global name at offset {} is not a string",
+                index, offset
+            ),
+            VerifyError::StackUnderflow { offset, op } => {
+                write!(f, "{:?} underflows the stack at offset {}", op, offset)
+            }
+            VerifyError::LocalOutOfRange {
+                offset,
+                slot,
+                depth,
+            } => write!(
+                f,
+                "Local slot {} out of range of stack depth {} at offset {}",
+                slot, depth, offset
+            ),
+            VerifyError::MissingReturn => write!(f, "Chunk must end with OP_RETURN"),
+        }
+    }
+}
+
+impl From<DecodeError> for VerifyError {
+    fn from(e: DecodeError) -> Self {
+        VerifyError::Decode(e)
+    }
+}
+
+/// Checks that a chunk is well-formed before it is executed: every opcode is known and has
+/// its operands, constant operands point into the pool (and name strings for global
+/// access), no instruction pops more values than the preceding code pushed, local slots
+/// address live stack values and the code ends with `OP_RETURN`.
+///
+/// Returns the maximum stack depth reached by the chunk.
+pub fn verify(chunk: &Chunk) -> Result<usize, VerifyError> {
+    let code = &chunk.code;
+    let constants = &chunk.const_pool.values;
+    let mut offset = 0;
+    let mut depth = 0;
+    let mut max_depth = 0;
+    let mut last = None;
+
+    while offset < code.len() {
+        let instruction = decode(code, offset)?;
+        let op = instruction.op;
+
+        if let Some(index) = instruction.operand {
+            if op.has_const_operand() {
+                match constants.get(index) {
+                    None => return Err(VerifyError::ConstantOutOfBounds { offset, index }),
+                    Some(Value::String(_)) => (),
+                    Some(_)
+                        if !matches!(
+                            op,
+                            OpCode::OP_CONST | OpCode::OP_CONST_LONG | OpCode::OP_ADD_CONST_LOCAL
+                        ) =>
+                    {
+                        return Err(VerifyError::InvalidGlobalName { offset, index })
+                    }
+                    Some(_) => (),
+                }
+            }
+        }
+
+        // Each local slot with the stack depth at the time it is accessed. The second local of
+        // OP_GET_LOCALS is read after the first one was pushed.
+        let slots = match op {
+            OpCode::OP_GET_LOCAL | OpCode::OP_SET_LOCAL => {
+                [(instruction.operand, depth), (None, 0)]
+            }
+            OpCode::OP_ADD_CONST_LOCAL => [(instruction.second_operand, depth), (None, 0)],
+            OpCode::OP_GET_LOCALS => [
+                (instruction.operand, depth),
+                (instruction.second_operand, depth + 1),
+            ],
+            _ => [(None, 0), (None, 0)],
+        };
+        for (slot, depth) in slots.iter() {
+            match slot {
+                Some(slot) if slot >= depth => {
+                    return Err(VerifyError::LocalOutOfRange {
+                        offset,
+                        slot: *slot,
+                        depth: *depth,
+                    })
+                }
+                _ => (),
+            }
+        }
+
+        let (pops, pushes) = op.stack_effect();
+        if pops > depth {
+            return Err(VerifyError::StackUnderflow { offset, op });
+        }
+        depth = depth - pops + pushes;
+        max_depth = max_depth.max(depth);
+
+        last = Some(op);
+        offset = instruction.next_offset();
+    }
+
+    match last {
+        Some(OpCode::OP_RETURN) => Ok(max_depth),
+        _ => Err(VerifyError::MissingReturn),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify, VerifyError};
+    use crate::bytecode::chunk::Chunk;
+    use crate::bytecode::instruction::DecodeError;
+    use crate::bytecode::opcode::OpCode;
+    use crate::value::value::Value;
+
+    fn chunk(code: &[u8], constants: Vec<Value>) -> Chunk {
+        let mut chunk = Chunk::new();
+        for byte in code {
+            chunk.write_byte(*byte, 1);
+        }
+        for constant in constants {
+            chunk.add_const(constant);
+        }
+
+        chunk
+    }
+
+    #[test]
+    fn test_valid_chunk() {
+        let valid = chunk(
+            &[
+                OpCode::OP_CONST as u8,
+                0,
+                OpCode::OP_GET_LOCAL as u8,
+                0,
+                OpCode::OP_ADD as u8,
+                OpCode::OP_DEF_GLOBAL as u8,
+                1,
+                OpCode::OP_RETURN as u8,
+            ],
+            vec![Value::Number(1.0), Value::from("a")],
+        );
+
+        assert_eq!(Ok(2), verify(&valid));
+
+        // The second local of OP_GET_LOCALS may be the one pushed by the first.
+        let locals = chunk(
+            &[
+                OpCode::OP_NIL as u8,
+                OpCode::OP_GET_LOCALS as u8,
+                0,
+                1,
+                OpCode::OP_RETURN as u8,
+            ],
+            vec![],
+        );
+        assert_eq!(Ok(3), verify(&locals));
+    }
+
+    #[test]
+    fn test_rejects_malformed_chunks() {
+        let unknown = chunk(&[250], vec![]);
+        assert_eq!(
+            Err(VerifyError::Decode(DecodeError::UnknownOpcode {
+                offset: 0,
+                byte: 250
+            })),
+            verify(&unknown)
+        );
+
+        let out_of_pool = chunk(
+            &[OpCode::OP_CONST as u8, 3, OpCode::OP_RETURN as u8],
+            vec![],
+        );
+        assert_eq!(
+            Err(VerifyError::ConstantOutOfBounds {
+                offset: 0,
+                index: 3
+            }),
+            verify(&out_of_pool)
+        );
+
+        let bad_name = chunk(
+            &[OpCode::OP_GET_GLOBAL as u8, 0, OpCode::OP_RETURN as u8],
+            vec![Value::Nil],
+        );
+        assert_eq!(
+            Err(VerifyError::InvalidGlobalName {
+                offset: 0,
+                index: 0
+            }),
+            verify(&bad_name)
+        );
+
+        let underflow = chunk(&[OpCode::OP_POP as u8, OpCode::OP_RETURN as u8], vec![]);
+        assert_eq!(
+            Err(VerifyError::StackUnderflow {
+                offset: 0,
+                op: OpCode::OP_POP
+            }),
+            verify(&underflow)
+        );
+
+        let bad_slot = chunk(
+            &[
+                OpCode::OP_NIL as u8,
+                OpCode::OP_SET_LOCAL as u8,
+                1,
+                OpCode::OP_RETURN as u8,
+            ],
+            vec![],
+        );
+        assert_eq!(
+            Err(VerifyError::LocalOutOfRange {
+                offset: 1,
+                slot: 1,
+                depth: 1
+            }),
+            verify(&bad_slot)
+        );
+
+        let bad_second_slot = chunk(
+            &[
+                OpCode::OP_NIL as u8,
+                OpCode::OP_GET_LOCALS as u8,
+                0,
+                2,
+                OpCode::OP_RETURN as u8,
+            ],
+            vec![],
+        );
+        assert_eq!(
+            Err(VerifyError::LocalOutOfRange {
+                offset: 1,
+                slot: 2,
+                depth: 2
+            }),
+            verify(&bad_second_slot)
+        );
+
+        let no_return = chunk(&[OpCode::OP_NIL as u8], vec![]);
+        assert_eq!(Err(VerifyError::MissingReturn), verify(&no_return));
+    }
+}
@@ -0,0 +1,249 @@
+use crate::bytecode::chunk::Chunk;
+use crate::bytecode::opcode::OpCode;
+use crate::compiler::compilation::Definition;
+use crate::compiler::emitter::{EmitResult, Emitter};
+use crate::compiler::scope::ScopeTracker;
+use crate::error::error::Error;
+use crate::scanner::token::{Token, TokenType};
+use crate::syntax::ast::{Expr, ExprKind, Identifier, Program, Stmt, StmtKind};
+use crate::value::value::Value;
+
+/// What the generator produced for a program.
+pub struct Generated {
+    pub chunk: Chunk,
+    pub definitions: Vec<Definition>,
+}
+
+/// Generates the bytecode of a parsed program. It emits exactly what the single-pass
+/// compiler emits for the same source, and reports the errors about names that the parser
+/// leaves out.
+pub struct CodeGenerator {
+    emitter: Emitter,
+    scope: ScopeTracker,
+    definitions: Vec<Definition>,
+    errors: Vec<Error>,
+    panic: bool,
+}
+
+impl CodeGenerator {
+    pub fn new() -> Self {
+        CodeGenerator {
+            emitter: Emitter::new(),
+            scope: ScopeTracker::new(),
+            definitions: Vec::new(),
+            errors: Vec::new(),
+            panic: false,
+        }
+    }
+
+    pub fn generate(mut self, program: &Program) -> Result<Generated, Vec<Error>> {
+        for statement in program.statements.iter() {
+            self.statement(statement);
+        }
+        self.emitter.emit_op(OpCode::OP_RETURN, program.line);
+
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
+
+        Ok(Generated {
+            chunk: self.emitter.finish(),
+            definitions: self.definitions,
+        })
+    }
+
+    fn error_at(&mut self, name: &Identifier, message: &str) {
+        if self.panic {
+            return;
+        }
+
+        self.panic = true;
+
+        let mut token = Token::new(TokenType::IDENTIFIER, name.name.clone(), name.line);
+        token.span = name.span;
+        self.errors.push(Error::new(token, message));
+    }
+
+    /// Reports a failure of the emitter, which has no token to point at.
+    fn check(&mut self, result: EmitResult<()>, line: usize) {
+        if let Err(message) = result {
+            if !self.panic {
+                self.panic = true;
+                self.errors
+                    .push(Error::new(Token::error(message, line), message));
+            }
+        }
+    }
+
+    fn identifier_const(&mut self, name: &str, line: usize) -> u8 {
+        match self.emitter.identifier_const(Value::from(name)) {
+            Ok(i) => i,
+            Err(message) => {
+                self.check(Err(message), line);
+                0
+            }
+        }
+    }
+}
+
+impl CodeGenerator {
+    fn statement(&mut self, statement: &Stmt) {
+        let line = statement.line;
+
+        match &statement.kind {
+            StmtKind::Var { name, initializer } => {
+                let global = self.declare_var(name);
+
+                match initializer {
+                    Some(initializer) => self.expression(initializer),
+                    None => self.emitter.emit_op(OpCode::OP_NIL, name.line),
+                }
+
+                if self.scope.is_global() {
+                    self.emitter
+                        .emit_op_with(OpCode::OP_DEF_GLOBAL, global, line);
+                } else {
+                    let slot = self.scope.define_last();
+                    self.emitter.begin_local(name.name.clone(), slot);
+                }
+            }
+            StmtKind::Print(expr) => {
+                self.expression(expr);
+                self.emitter.emit_op(OpCode::OP_PRINT, line);
+            }
+            StmtKind::Expression(expr) => {
+                self.expression(expr);
+                self.emitter.emit_op(OpCode::OP_POP, line);
+            }
+            StmtKind::Echo(expr) => {
+                self.expression(expr);
+
+                let result = self.identifier_const("_", line);
+                self.definitions.push(Definition {
+                    name: String::from("_"),
+                    line,
+                    input: 0,
+                });
+                self.emitter
+                    .emit_op_with(OpCode::OP_DEF_GLOBAL, result, line);
+                self.emitter
+                    .emit_op_with(OpCode::OP_GET_GLOBAL, result, line);
+                self.emitter.emit_op(OpCode::OP_ECHO, line);
+            }
+            StmtKind::Block(statements) => {
+                self.scope.begin();
+                for statement in statements.iter() {
+                    self.statement(statement);
+                }
+                let pop_count = self.scope.end();
+                self.emitter.end_locals(pop_count as usize);
+                for _ in 0..pop_count {
+                    self.emitter.emit_op(OpCode::OP_POP, line);
+                }
+            }
+        }
+
+        // Like the parser, report at most one error per statement.
+        self.panic = false;
+    }
+
+    /// Declares the variable `name`, returning the constant of its name if it is a global.
+    fn declare_var(&mut self, name: &Identifier) -> u8 {
+        if self.scope.is_global() {
+            self.definitions.push(Definition {
+                name: name.name.clone(),
+                line: name.line,
+                input: 0,
+            });
+
+            return self.identifier_const(&name.name, name.line);
+        }
+
+        if self.scope.is_declared_in_current(&name.name) {
+            self.error_at(
+                name,
+                &format!(
+                    "Variable with name '{}' already declared in this scope",
+                    name.name
+                ),
+            );
+        }
+
+        let mut token = Token::new(TokenType::IDENTIFIER, name.name.clone(), name.line);
+        token.span = name.span;
+        if let Err(message) = self.scope.add_local(token) {
+            self.error_at(name, message);
+        }
+
+        0
+    }
+
+    fn expression(&mut self, expr: &Expr) {
+        let line = expr.line;
+
+        match &expr.kind {
+            ExprKind::Number(number) => {
+                let result = self.emitter.emit_const(Value::Number(*number), line);
+                self.check(result, line);
+            }
+            ExprKind::String(string) => {
+                let result = self.emitter.emit_const(Value::from(string.as_str()), line);
+                self.check(result, line);
+            }
+            ExprKind::Bool(true) => self.emitter.emit_op(OpCode::OP_TRUE, line),
+            ExprKind::Bool(false) => self.emitter.emit_op(OpCode::OP_FALSE, line),
+            ExprKind::Nil => self.emitter.emit_op(OpCode::OP_NIL, line),
+            ExprKind::Variable(name) => {
+                let (get_op, _, arg) = self.resolve(name);
+                self.emitter.emit_op_with(get_op, arg, line);
+            }
+            ExprKind::Assign { name, value } => {
+                let (_, set_op, arg) = self.resolve(name);
+                self.expression(value);
+                self.emitter.emit_op_with(set_op, arg, line);
+            }
+            ExprKind::Unary { op, operand } => {
+                let start = self.emitter.len();
+                self.expression(operand);
+
+                let result = self.emitter.emit_unary(*op, start, line);
+                self.check(result, line);
+            }
+            ExprKind::Binary { op, left, right } => {
+                let left_start = self.emitter.len();
+                self.expression(left);
+                let right_start = self.emitter.len();
+                self.expression(right);
+
+                let result = self.emitter.emit_binary(*op, left_start, right_start, line);
+                self.check(result, line);
+            }
+            ExprKind::Grouping(expr) => self.expression(expr),
+            ExprKind::Invalid => (),
+        }
+    }
+
+    /// The instructions that read and write the variable `name`, with their operand.
+    fn resolve(&mut self, name: &Identifier) -> (OpCode, OpCode, u8) {
+        match self
+            .scope
+            .locate_local(|local_name| local_name == name.name)
+        {
+            Some((slot, true)) => (OpCode::OP_GET_LOCAL, OpCode::OP_SET_LOCAL, slot as u8),
+            local => {
+                if local.is_some() {
+                    self.error_at(
+                        name,
+                        &format!(
+                            "Cannot read local variable '{}' in its own initializer",
+                            name.name
+                        ),
+                    );
+                }
+
+                let global = self.identifier_const(&name.name, name.line);
+                (OpCode::OP_GET_GLOBAL, OpCode::OP_SET_GLOBAL, global)
+            }
+        }
+    }
+}
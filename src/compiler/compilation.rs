@@ -1,16 +1,41 @@
-use crate::bytecode::chunk::Chunk;
-use std::collections::HashSet;
+use std::collections::HashMap;
+
+/// A global declared by one of the inputs of a compilation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    pub name: String,
+    pub line: usize,
+    /// Zero based index of the input that declared it.
+    pub input: usize,
+}
 
 pub struct Compilation {
-    pub chunk: Option<Chunk>,
-    pub intern_str: HashSet<String>,
+    pub definitions: HashMap<String, Definition>,
+    pub inputs: usize,
 }
 
 impl Compilation {
     pub fn new() -> Self {
         Compilation {
-            chunk: None,
-            intern_str: HashSet::new(),
+            definitions: HashMap::new(),
+            inputs: 0,
+        }
+    }
+
+    pub fn definition(&self, name: &str) -> Option<&Definition> {
+        self.definitions.get(name)
+    }
+
+    /// Records what a successfully compiled input declared. A later definition of the same
+    /// name replaces the earlier one.
+    pub fn commit(&mut self, definitions: impl Iterator<Item = Definition>) {
+        for definition in definitions {
+            let definition = Definition {
+                input: self.inputs,
+                ..definition
+            };
+            self.definitions.insert(definition.name.clone(), definition);
         }
+        self.inputs += 1;
     }
 }
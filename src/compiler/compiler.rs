@@ -1,29 +1,46 @@
 use crate::bytecode::chunk::Chunk;
 use crate::bytecode::opcode::OpCode;
-use crate::bytecode::opcode::OpCode::OP_CONST;
-use crate::compiler::compilation::Compilation;
-use crate::compiler::precedence::{get_rule, ParseFn, ParseRule, Precedence};
+use crate::compiler::codegen::CodeGenerator;
+use crate::compiler::compilation::{Compilation, Definition};
+use crate::compiler::emitter::Emitter;
+use crate::compiler::lint::{lint, LintConfig};
+use crate::compiler::peephole::{optimize, OptLevel};
+use crate::compiler::precedence::{get_rule, ParseFn, Precedence};
 use crate::compiler::scope::ScopeTracker;
-use crate::debug::disassembler::disassemble_chunk;
 use crate::error::error::Error;
 use crate::error::interpreter::InterpreterError;
 use crate::interpreter::interpreter::InterpreterResult;
+use crate::output::sink::Sink;
 use crate::scanner::scanner::Scanner;
 use crate::scanner::token::{Token, TokenType};
-use crate::value::obj::Obj;
-use crate::value::obj_str::ObjStr;
+use crate::syntax::ast::{BinaryOp, UnaryOp};
+use crate::syntax::parser::Parser;
 use crate::value::value::Value;
-use std::collections::HashSet;
+use std::io::Write;
+
+// Deeper nesting of blocks and expressions would overflow the native stack of the parser.
+const MAX_NESTING: usize = 256;
 
 pub struct Compiler {
     current: Option<Token>,
     previous: Option<Token>,
     scanner: Option<Scanner>,
-    chunk: Option<Chunk>,
+    emitter: Option<Emitter>,
     errors: Vec<InterpreterError>,
     panic: bool,
-    compilation: Option<Compilation>,
+    definitions: Vec<Definition>,
     scope: ScopeTracker,
+    diagnostics: Sink,
+    echo: bool,
+    nesting: usize,
+    // Set when the rest of the input was skipped, later errors would only be consequences.
+    aborted: bool,
+    // Where the code of the left operand of the infix operator being compiled starts.
+    operand_start: usize,
+    opt_level: OptLevel,
+    single_pass: bool,
+    lints: Option<LintConfig>,
+    warnings: Sink,
 }
 
 impl Compiler {
@@ -34,40 +51,146 @@ impl Compiler {
             scanner: None,
             errors: Vec::new(),
             panic: false,
-            chunk: None,
-            compilation: Some(Compilation::new()),
+            emitter: None,
+            definitions: Vec::new(),
             scope: ScopeTracker::new(),
+            diagnostics: Sink::stderr(),
+            echo: false,
+            nesting: 0,
+            aborted: false,
+            operand_start: 0,
+            opt_level: OptLevel::default(),
+            single_pass: false,
+            lints: None,
+            warnings: Sink::stderr(),
         }
     }
 
-    pub fn compile(&mut self, source: &str) -> InterpreterResult<Compilation> {
+    /// Redirects the compile errors reported while parsing.
+    pub fn set_diagnostics(&mut self, diagnostics: Sink) {
+        self.diagnostics = diagnostics;
+    }
+
+    /// When enabled, a trailing expression without `;` shows its value and binds it to the
+    /// `_` global instead of being a syntax error, as the REPL wants.
+    pub fn set_echo(&mut self, echo: bool) {
+        self.echo = echo;
+    }
+
+    pub fn set_opt_level(&mut self, opt_level: OptLevel) {
+        self.opt_level = opt_level;
+    }
+
+    /// When enabled, code is emitted while parsing instead of from a syntax tree. Both
+    /// produce the same chunk, the single pass does it faster.
+    pub fn set_single_pass(&mut self, single_pass: bool) {
+        self.single_pass = single_pass;
+    }
+
+    /// Checks the source for the lints of `config` once it compiled, see `lint::lint`.
+    /// Only the syntax tree is linted, not the single pass.
+    pub fn set_lints(&mut self, config: Option<LintConfig>) {
+        self.lints = config;
+    }
+
+    /// Redirects the warnings of the lints.
+    pub fn set_warnings(&mut self, warnings: Sink) {
+        self.warnings = warnings;
+    }
+
+    pub fn compile(&mut self, source: &str) -> InterpreterResult<Chunk> {
+        self.compile_into(source, &mut Compilation::new())
+    }
+
+    /// Compiles `source` as the next input of `compilation`, which keeps the global
+    /// definitions of every input that compiled successfully.
+    pub fn compile_into(
+        &mut self,
+        source: &str,
+        compilation: &mut Compilation,
+    ) -> InterpreterResult<Chunk> {
+        let compiled = if self.single_pass {
+            self.compile_single_pass(source)
+        } else {
+            self.compile_tree(source)
+        };
+
+        let comp = compiled.map(|chunk| {
+            compilation.commit(self.definitions.drain(..));
+
+            if self.opt_level >= OptLevel::Peephole {
+                optimize(chunk, self.opt_level)
+            } else {
+                chunk
+            }
+        });
+
+        self.reset();
+
+        comp
+    }
+
+    fn compile_single_pass(&mut self, source: &str) -> InterpreterResult<Chunk> {
         self.scanner = Some(Scanner::new(source));
-        self.chunk = Some(Chunk::new());
+        self.emitter = Some(Emitter::new());
 
         self.advance();
         while !self.match_advance(TokenType::EOF) {
-            println!("loop");
             self.declaration();
         }
 
         self.emit_byte(OpCode::OP_RETURN as u8);
 
-        if self.errors.len() != 0 {
-            Err(self.errors.pop().unwrap())
+        match self.errors.pop() {
+            Some(error) => Err(error),
+            None => Ok(self.emitter.take().unwrap().finish()),
+        }
+    }
+
+    /// Parses `source` into a syntax tree first and generates the code from it. Errors about
+    /// names are only reported when the source parsed without errors.
+    fn compile_tree(&mut self, source: &str) -> InterpreterResult<Chunk> {
+        let mut parser = Parser::new(source);
+        parser.set_echo(self.echo);
+        parser.set_keep_comments(self.lints.is_some());
+
+        let (program, errors) = parser.parse();
+        let mut errors = if errors.is_empty() {
+            match CodeGenerator::new().generate(&program) {
+                Ok(generated) => {
+                    if let Some(config) = self.lints.as_ref() {
+                        for warning in lint(&program, config) {
+                            let _ = writeln!(self.warnings, "{}", warning);
+                        }
+                    }
+
+                    self.definitions = generated.definitions;
+                    return Ok(generated.chunk);
+                }
+                Err(errors) => errors,
+            }
         } else {
-            self.compilation.as_mut().unwrap().chunk = Some(self.chunk.take().unwrap());
-            let comp = Ok(self.compilation.take().unwrap());
-
-            self.reset();
+            errors
+        };
 
-            comp
+        for error in errors.iter() {
+            let _ = writeln!(self.diagnostics, "{}", error);
         }
+
+        Err(InterpreterError::CompilerError(errors.pop().unwrap()))
     }
 
-    fn emit_byte(&mut self, byte: u8) {
-        let line = self.previous.as_ref().unwrap().line;
+    fn emitter(&mut self) -> &mut Emitter {
+        self.emitter.as_mut().unwrap()
+    }
 
-        self.chunk.as_mut().unwrap().write_byte(byte, line)
+    fn line(&self) -> usize {
+        self.previous.as_ref().unwrap().line
+    }
+
+    fn emit_byte(&mut self, byte: u8) {
+        let line = self.line();
+        self.emitter().emit_byte(byte, line)
     }
 
     fn emit_bytes(&mut self, byte: u8, byte_operand: u8) {
@@ -75,22 +198,26 @@ impl Compiler {
         self.emit_byte(byte_operand);
     }
 
-    fn emit_many(&mut self, bytes: &[u8]) {
-        for b in bytes {
-            self.emit_byte(*b)
+    fn emit_const(&mut self, value: Value) {
+        let line = self.line();
+        if let Err(message) = self.emitter().emit_const(value, line) {
+            self.error_at_previous(message);
         }
     }
 
-    fn emit_const(&mut self, value: Value) {
-        let i = self.make_const(value);
-
-        self.emit_bytes(OpCode::OP_CONST as u8, i)
+    /// Adds a constant that is addressed by a single byte operand, such as a global name.
+    fn identifier_const(&mut self, value: Value) -> u8 {
+        match self.emitter().identifier_const(value) {
+            Ok(i) => i,
+            Err(message) => {
+                self.error_at_previous(message);
+                0
+            }
+        }
     }
 
-    fn make_const(&mut self, value: Value) -> u8 {
-        let i = self.chunk.as_mut().unwrap().add_const(value);
-
-        i as u8
+    fn code_len(&self) -> usize {
+        self.emitter.as_ref().unwrap().len()
     }
 
     fn advance(&mut self) {
@@ -113,22 +240,21 @@ impl Compiler {
     }
 
     fn error_at_previous(&mut self, message: &str) {
-        let token = self.current.as_ref().unwrap().clone();
+        let token = self.previous.as_ref().unwrap().clone();
         self.error_at(token, message)
     }
 
     fn error_at(&mut self, token: Token, message: &str) {
-        if self.panic {
+        if self.panic || self.aborted {
             return;
         }
 
         self.panic = true;
 
-        let error = InterpreterError::CompilerError(Error::new(token, message));
-
-        eprintln!("{}", error);
+        let error = Error::new(token, message);
+        let _ = writeln!(self.diagnostics, "{}", error);
 
-        self.errors.push(error);
+        self.errors.push(InterpreterError::CompilerError(error));
     }
 
     fn consume_if_expected(&mut self, token_type: TokenType, message: &str) {
@@ -142,25 +268,26 @@ impl Compiler {
 
 impl Compiler {
     fn parse_precedence(&mut self, level: Precedence) {
+        if self.enter_nesting() {
+            self.parse_nested_precedence(level);
+        }
+        self.nesting -= 1;
+    }
+
+    fn parse_nested_precedence(&mut self, level: Precedence) {
         self.advance();
 
         let prefix_rule = get_rule(&self.previous.as_ref().unwrap().token_type);
         if prefix_rule.prefix.is_none() {
-            self.error_at_current("Expect expression");
+            self.error_at_previous("Expect expression");
             return;
         }
 
         let is_assignable = level as u8 <= Precedence::ASSIGNMENT as u8;
 
+        let start = self.code_len();
         self.dispatch(prefix_rule.prefix.as_ref().unwrap(), is_assignable);
 
-        let prec_level = level;
-        let prev_level = get_rule(&self.current.as_ref().unwrap().token_type).precedence;
-        println!(
-            "CALL: {:?} {} CURRENT: {:?} {}",
-            prec_level, prec_level as u8, prev_level, prev_level as u8
-        );
-
         while level as u8 <= get_rule(&self.current.as_ref().unwrap().token_type).precedence as u8 {
             self.advance();
 
@@ -169,11 +296,12 @@ impl Compiler {
                 .as_ref()
                 .unwrap();
 
+            self.operand_start = start;
             self.dispatch(infix_rule, is_assignable);
         }
 
         if is_assignable && self.match_advance(TokenType::EQUAL) {
-            self.error_at_current("Invalid assignment target.");
+            self.error_at_previous("Invalid assignment target.");
         }
     }
 
@@ -195,10 +323,9 @@ impl Compiler {
 
     fn number(&mut self) {
         let value = self.previous.as_ref().unwrap().lexem.parse::<f64>();
-        if let Err(e) = value {
-            self.error_at_previous("Invalid number");
-        } else {
-            self.emit_const(Value::Number(value.unwrap()));
+        match value {
+            Ok(number) => self.emit_const(Value::Number(number)),
+            Err(_) => self.error_at_previous("Invalid number"),
         }
     }
 
@@ -209,19 +336,23 @@ impl Compiler {
     }
 
     fn unary(&mut self) {
-        let token_type = self.previous.as_ref().unwrap().token_type.clone();
+        let op = UnaryOp::from_token(self.previous.as_ref().unwrap().token_type);
 
+        let start = self.code_len();
         self.parse_precedence(Precedence::UNARY);
 
-        match token_type {
-            TokenType::MINUS => self.emit_byte(OpCode::OP_NEGATE as u8),
-            TokenType::BANG => self.emit_byte(OpCode::OP_NOT as u8),
-            _ => (),
+        if let Some(op) = op {
+            let line = self.line();
+            if let Err(message) = self.emitter().emit_unary(op, start, line) {
+                self.error_at_previous(message);
+            }
         }
     }
 
     fn binary(&mut self) {
-        let op = self.previous.as_ref().unwrap().token_type.clone();
+        let op = self.previous.as_ref().unwrap().token_type;
+        let left_start = self.operand_start;
+        let right_start = self.code_len();
 
         let rule = get_rule(&op);
         let next_prec = rule.get_incremented_prec(1u8);
@@ -231,23 +362,14 @@ impl Compiler {
 
         self.parse_precedence(rule.get_incremented_prec(1u8).unwrap());
 
-        match op {
-            TokenType::PLUS => self.emit_byte(OpCode::OP_ADD as u8),
-            TokenType::MINUS => self.emit_byte(OpCode::OP_SUB as u8),
-            TokenType::STAR => self.emit_byte(OpCode::OP_MUL as u8),
-            TokenType::SLASH => self.emit_byte(OpCode::OP_DIV as u8),
-            TokenType::BANG_EQUAL => self.emit_bytes(OpCode::OP_EQUAL as u8, OpCode::OP_NOT as u8),
-            TokenType::EQUAL_EQUAL => self.emit_byte(OpCode::OP_EQUAL as u8),
-            TokenType::GREATER => self.emit_byte(OpCode::OP_GREATER as u8),
-            TokenType::GREATER_EQUAL => {
-                self.emit_bytes(OpCode::OP_LESS as u8, OpCode::OP_NOT as u8)
+        if let Some(op) = BinaryOp::from_token(op) {
+            let line = self.line();
+            if let Err(message) = self
+                .emitter()
+                .emit_binary(op, left_start, right_start, line)
+            {
+                self.error_at_previous(message);
             }
-            TokenType::LESS => self.emit_byte(OpCode::OP_LESS as u8),
-            TokenType::LESS_EQUAL => {
-                self.emit_bytes(OpCode::OP_GREATER as u8, OpCode::OP_NOT as u8)
-            }
-
-            _ => return,
         }
     }
 
@@ -256,7 +378,7 @@ impl Compiler {
             TokenType::FALSE => self.emit_byte(OpCode::OP_FALSE as u8),
             TokenType::NIL => self.emit_byte(OpCode::OP_NIL as u8),
             TokenType::TRUE => self.emit_byte(OpCode::OP_TRUE as u8),
-            _ => return,
+            _ => (),
         }
     }
 
@@ -273,11 +395,17 @@ impl Compiler {
     }
 
     fn declaration(&mut self) {
+        if !self.enter_nesting() {
+            self.nesting -= 1;
+            return;
+        }
+
         if self.match_advance(TokenType::VAR) {
             self.var_declaration();
         } else {
             self.statement();
         }
+        self.nesting -= 1;
 
         if self.panic {
             self.synchronize();
@@ -290,7 +418,7 @@ impl Compiler {
         } else if self.match_advance(TokenType::LEFT_BRACE) {
             self.scope.begin();
             self.block();
-            self.scope.end();
+            self.end_scope();
         } else {
             self.expression_statement();
         }
@@ -304,9 +432,28 @@ impl Compiler {
         self.consume_if_expected(TokenType::RIGHT_BRACE, "Expect '}' after block.");
     }
 
+    /// Counts one more level of nesting. Past the limit it reports an error and skips the
+    /// rest of the input, so the callers unwind without recursing any further.
+    fn enter_nesting(&mut self) -> bool {
+        self.nesting += 1;
+        if self.nesting <= MAX_NESTING {
+            return true;
+        }
+
+        self.error_at_current("Too much nesting");
+        self.aborted = true;
+        while !self.check(TokenType::EOF) {
+            self.advance();
+        }
+
+        false
+    }
+
     fn reset(&mut self) {
-        self.chunk = None;
-        self.compilation = Some(Compilation::new());
+        self.nesting = 0;
+        self.aborted = false;
+        self.emitter = None;
+        self.definitions.clear();
         self.previous = None;
         self.current = None;
         self.panic = false;
@@ -317,8 +464,9 @@ impl Compiler {
 
     fn end_scope(&mut self) {
         let pop_count = self.scope.end();
+        self.emitter().end_locals(pop_count as usize);
         for _ in 0..pop_count {
-           self.emit_byte(OpCode::OP_POP as u8);
+            self.emit_byte(OpCode::OP_POP as u8);
         }
     }
 }
@@ -333,6 +481,20 @@ impl Compiler {
 
     fn expression_statement(&mut self) {
         self.expression();
+
+        if self.echo && self.scope.is_global() && self.check(TokenType::EOF) {
+            let result = self.identifier_const(Value::from("_"));
+            self.definitions.push(Definition {
+                name: String::from("_"),
+                line: self.previous.as_ref().unwrap().line,
+                input: 0,
+            });
+            self.emit_bytes(OpCode::OP_DEF_GLOBAL as u8, result);
+            self.emit_bytes(OpCode::OP_GET_GLOBAL as u8, result);
+            self.emit_byte(OpCode::OP_ECHO as u8);
+            return;
+        }
+
         self.consume_if_expected(TokenType::SEMICOLON, "Expect ';' after expression.");
         self.emit_byte(OpCode::OP_POP as u8);
     }
@@ -362,7 +524,13 @@ impl Compiler {
             return 0;
         }
 
-        self.make_const(Value::from(var_name.as_str()))
+        self.definitions.push(Definition {
+            name: var_name.clone(),
+            line: self.previous.as_ref().unwrap().line,
+            input: 0,
+        });
+
+        self.identifier_const(Value::from(var_name.as_str()))
     }
 
     fn declare_var(&mut self) {
@@ -372,53 +540,57 @@ impl Compiler {
 
         let name = self.previous.as_ref().unwrap().clone();
 
-        if self.scope.locate_local(|local_name|  {
-            local_name == &name.lexem
-        }) != -1 {
-            self.error_at_current(&format!("Variable with name '{}' already declared in this scope", name.lexem));
+        if self.scope.is_declared_in_current(&name.lexem) {
+            self.error_at_previous(&format!(
+                "Variable with name '{}' already declared in this scope",
+                name.lexem
+            ));
         }
 
-        self.scope.add_local(name);
+        if let Err(message) = self.scope.add_local(name) {
+            self.error_at_previous(message);
+        }
     }
 
     fn define_var(&mut self, global: u8) {
         if !self.scope.is_global() {
-            self.scope.define_last();
+            let slot = self.scope.define_last();
+            let name = self.scope.name_of(slot).to_string();
+            self.emitter().begin_local(name, slot);
             return;
         }
         self.emit_bytes(OpCode::OP_DEF_GLOBAL as u8, global)
     }
 
     fn named_variable(&mut self, name: &str, is_assignable: bool) {
-        let arg = self.resolve_local(name);
-        let get_op = OpCode::OP_GET_LOCAL as u8;
-        let set_op = OpCode::OP_SET_LOCAL as u8;
-        if arg != -1 {
-            ()
+        let slot = self.resolve_local(name);
+        let (get_op, set_op, arg) = if slot != -1 {
+            (OpCode::OP_GET_LOCAL, OpCode::OP_SET_LOCAL, slot as u8)
         } else {
-            let arg = self.make_const(Value::from(name)) as i64;
-            let get_op = OpCode::OP_GET_GLOBAL as u8;
-            let set_op = OpCode::OP_SET_GLOBAL as u8;
-        }
+            let global = self.identifier_const(Value::from(name));
+            (OpCode::OP_GET_GLOBAL, OpCode::OP_SET_GLOBAL, global)
+        };
 
         if is_assignable && self.match_advance(TokenType::EQUAL) {
             self.expression();
-            self.emit_bytes(set_op, arg as u8);
+            self.emit_bytes(set_op as u8, arg);
         } else {
-            self.emit_bytes(get_op, arg as u8);
+            self.emit_bytes(get_op as u8, arg);
         }
     }
 
     fn resolve_local(&mut self, name: &str) -> i64 {
-        let i = self.scope.locate_local(|local_name| {
-            local_name == name
-        });
-
-        if i == -1 {
-            self.error_at_current(&format!("Cannot read local variable '{}' in its own initializer", name));
+        match self.scope.locate_local(|local_name| local_name == name) {
+            Some((_, false)) => {
+                self.error_at_previous(&format!(
+                    "Cannot read local variable '{}' in its own initializer",
+                    name
+                ));
+                -1
+            }
+            Some((slot, true)) => slot as i64,
+            None => -1,
         }
-
-        i
     }
 }
 
@@ -449,11 +621,16 @@ impl Compiler {
             }
 
             match self.current.as_ref().unwrap().token_type {
-                TokenType::RETURN => return,
-                _ => (),
-            };
-
-            self.advance();
+                TokenType::CLASS
+                | TokenType::FUN
+                | TokenType::VAR
+                | TokenType::FOR
+                | TokenType::IF
+                | TokenType::WHILE
+                | TokenType::PRINT
+                | TokenType::RETURN => return,
+                _ => self.advance(),
+            }
         }
     }
 
@@ -466,3 +643,109 @@ impl Compiler {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Compiler;
+    use crate::bytecode::chunk::Chunk;
+    use crate::bytecode::opcode::OpCode;
+    use crate::compiler::peephole::OptLevel;
+    use crate::output::sink::OutputBuffer;
+
+    fn compile(source: &str) -> Chunk {
+        Compiler::new().compile(source).unwrap()
+    }
+
+    #[test]
+    fn test_constant_folding() {
+        let chunk = compile("print 1 + 2 * -3;");
+        assert_eq!(4, chunk.code.len());
+        assert_eq!(OpCode::OP_CONST as u8, chunk.code[0]);
+        assert_eq!(
+            "-5",
+            chunk.const_pool.values[chunk.code[1] as usize].to_string()
+        );
+
+        let chunk = compile("print !(\"a\" + \"b\" == \"c\");");
+        assert_eq!(
+            vec![
+                OpCode::OP_TRUE as u8,
+                OpCode::OP_PRINT as u8,
+                OpCode::OP_RETURN as u8
+            ],
+            chunk.code
+        );
+        assert_eq!(chunk.code.len(), chunk.line_code_index().iter().sum());
+
+        assert!(compile("print \"a\" - 1;")
+            .code
+            .contains(&(OpCode::OP_SUB as u8)));
+        assert!(compile("var a; print a + 1 + 2;")
+            .code
+            .contains(&(OpCode::OP_ADD as u8)));
+    }
+
+    #[test]
+    fn test_single_pass_matches_tree() {
+        let source = "var a = 1;\n{ var b = a + -2;\n  { var c = b; b = c *\n 3; }\n print b >= 2; }\na = \"x\" + \"y\";\na";
+
+        let chunks: Vec<Chunk> = [true, false]
+            .iter()
+            .map(|single_pass| {
+                let mut compiler = Compiler::new();
+                compiler.set_opt_level(OptLevel::None);
+                compiler.set_echo(true);
+                compiler.set_single_pass(*single_pass);
+                compiler.compile(source).unwrap()
+            })
+            .collect();
+
+        assert_eq!(chunks[0].code, chunks[1].code);
+        assert_eq!(chunks[0].line_code_index(), chunks[1].line_code_index());
+        assert_eq!(
+            format!("{:?}", chunks[0].const_pool.values),
+            format!("{:?}", chunks[1].const_pool.values)
+        );
+    }
+
+    #[test]
+    fn test_tree_reports_name_errors() {
+        let diagnostics = OutputBuffer::new();
+        let mut compiler = Compiler::new();
+        compiler.set_diagnostics(diagnostics.sink());
+
+        assert!(compiler
+            .compile("{ var a; var a; }\n{ var b = b; }")
+            .is_err());
+        assert_eq!(
+            "[line 1] Error at 'a': Variable with name 'a' already declared in this scope\n\
+             [line 2] Error at 'b': Cannot read local variable 'b' in its own initializer\n",
+            diagnostics.take()
+        );
+    }
+
+    #[test]
+    fn test_too_many_locals() {
+        let declarations = |count: usize| -> String {
+            (0..count)
+                .map(|i| format!("var v{} = {};\n", i, i))
+                .collect()
+        };
+        let full = format!("{{\n{}print v255;\n}}", declarations(256));
+        let over = format!("{{\n{}}}", declarations(257));
+
+        for single_pass in [true, false] {
+            let diagnostics = OutputBuffer::new();
+            let mut compiler = Compiler::new();
+            compiler.set_diagnostics(diagnostics.sink());
+            compiler.set_single_pass(single_pass);
+
+            assert!(compiler.compile(&full).is_ok());
+            assert!(compiler.compile(&over).is_err());
+            assert_eq!(
+                "[line 258] Error at 'v256': Too many local variables in scope\n",
+                diagnostics.take()
+            );
+        }
+    }
+}
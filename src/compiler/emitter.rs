@@ -0,0 +1,217 @@
+use crate::bytecode::chunk::{Chunk, LocalInfo};
+use crate::bytecode::instruction::decode;
+use crate::bytecode::opcode::OpCode;
+use crate::compiler::fold::{fold_binary, fold_unary};
+use crate::syntax::ast::{BinaryOp, UnaryOp};
+use crate::value::value::Value;
+
+pub type EmitResult<T> = Result<T, &'static str>;
+
+/// Writes instructions and constants into a chunk, for both the single-pass compiler and
+/// the generator that works on syntax trees. Operators on constant operands are folded
+/// as they are emitted.
+pub struct Emitter {
+    chunk: Chunk,
+    /// Indices in the locals of the chunk of those still in scope.
+    open_locals: Vec<usize>,
+}
+
+impl Emitter {
+    pub fn new() -> Self {
+        Emitter {
+            chunk: Chunk::new(),
+            open_locals: Vec::new(),
+        }
+    }
+
+    pub fn finish(self) -> Chunk {
+        self.chunk
+    }
+
+    /// Offset of the next instruction.
+    pub fn len(&self) -> usize {
+        self.chunk.code.len()
+    }
+
+    pub fn emit_byte(&mut self, byte: u8, line: usize) {
+        self.chunk.write_byte(byte, line)
+    }
+
+    pub fn emit_op(&mut self, op: OpCode, line: usize) {
+        self.chunk.write_code(op, line)
+    }
+
+    pub fn emit_op_with(&mut self, op: OpCode, operand: u8, line: usize) {
+        self.emit_op(op, line);
+        self.emit_byte(operand, line);
+    }
+
+    pub fn emit_const(&mut self, value: Value, line: usize) -> EmitResult<()> {
+        let i = self.make_const(value);
+
+        if i <= u8::MAX as usize {
+            self.emit_op_with(OpCode::OP_CONST, i as u8, line)
+        } else if i < 1 << 24 {
+            self.emit_op(OpCode::OP_CONST_LONG, line);
+            for byte in i.to_le_bytes().iter().take(3) {
+                self.emit_byte(*byte, line);
+            }
+        } else {
+            return Err("Too many constants in one chunk");
+        }
+
+        Ok(())
+    }
+
+    /// Records that the local `name` holds its value in stack slot `slot` from here on.
+    pub fn begin_local(&mut self, name: String, slot: usize) {
+        let start = self.len();
+        self.chunk.locals.push(LocalInfo {
+            name,
+            slot,
+            start,
+            end: start,
+        });
+        self.open_locals.push(self.chunk.locals.len() - 1);
+    }
+
+    /// Records that the last `count` locals begun go out of scope here.
+    pub fn end_locals(&mut self, count: usize) {
+        let end = self.len();
+        for _ in 0..count {
+            if let Some(i) = self.open_locals.pop() {
+                self.chunk.locals[i].end = end;
+            }
+        }
+    }
+
+    /// Adds a constant that is addressed by a single byte operand, such as a global name.
+    pub fn identifier_const(&mut self, value: Value) -> EmitResult<u8> {
+        let i = self.make_const(value);
+        if i > u8::MAX as usize {
+            return Err("Too many global names in one chunk");
+        }
+
+        Ok(i as u8)
+    }
+
+    /// Emits the code that pushes a constant.
+    pub fn emit_value(&mut self, value: Value, line: usize) -> EmitResult<()> {
+        match value {
+            Value::Bool(true) => self.emit_op(OpCode::OP_TRUE, line),
+            Value::Bool(false) => self.emit_op(OpCode::OP_FALSE, line),
+            Value::Nil => self.emit_op(OpCode::OP_NIL, line),
+            value => return self.emit_const(value, line),
+        }
+
+        Ok(())
+    }
+
+    /// Emits `op` on the operand whose code starts at `start`, or replaces that code with
+    /// the result if the operand is a constant.
+    pub fn emit_unary(&mut self, op: UnaryOp, start: usize, line: usize) -> EmitResult<()> {
+        let folded = self
+            .constant_between(start, self.len())
+            .and_then(|operand| fold_unary(op, &operand));
+        if let Some(value) = folded {
+            return self.replace_with_constant(start, value, line);
+        }
+
+        match op {
+            UnaryOp::Negate => self.emit_op(OpCode::OP_NEGATE, line),
+            UnaryOp::Not => self.emit_op(OpCode::OP_NOT, line),
+        }
+
+        Ok(())
+    }
+
+    /// Emits `op` on the operands whose code starts at `left_start` and `right_start`, or
+    /// replaces that code with the result if both operands are constants.
+    pub fn emit_binary(
+        &mut self,
+        op: BinaryOp,
+        left_start: usize,
+        right_start: usize,
+        line: usize,
+    ) -> EmitResult<()> {
+        let left = self.constant_between(left_start, right_start);
+        let right = self.constant_between(right_start, self.len());
+        if let (Some(left), Some(right)) = (left, right) {
+            if let Some(value) = fold_binary(op, &left, &right) {
+                return self.replace_with_constant(left_start, value, line);
+            }
+        }
+
+        match op {
+            BinaryOp::Add => self.emit_op(OpCode::OP_ADD, line),
+            BinaryOp::Subtract => self.emit_op(OpCode::OP_SUB, line),
+            BinaryOp::Multiply => self.emit_op(OpCode::OP_MUL, line),
+            BinaryOp::Divide => self.emit_op(OpCode::OP_DIV, line),
+            BinaryOp::NotEqual => {
+                self.emit_op(OpCode::OP_EQUAL, line);
+                self.emit_op(OpCode::OP_NOT, line);
+            }
+            BinaryOp::Equal => self.emit_op(OpCode::OP_EQUAL, line),
+            BinaryOp::Greater => self.emit_op(OpCode::OP_GREATER, line),
+            BinaryOp::GreaterEqual => {
+                self.emit_op(OpCode::OP_LESS, line);
+                self.emit_op(OpCode::OP_NOT, line);
+            }
+            BinaryOp::Less => self.emit_op(OpCode::OP_LESS, line),
+            BinaryOp::LessEqual => {
+                self.emit_op(OpCode::OP_GREATER, line);
+                self.emit_op(OpCode::OP_NOT, line);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The value pushed by the code from `start` to `end`, if that is a single constant.
+    fn constant_between(&self, start: usize, end: usize) -> Option<Value> {
+        let chunk = &self.chunk;
+        if start >= end || end > chunk.code.len() {
+            return None;
+        }
+
+        let instruction = decode(&chunk.code, start).ok()?;
+        if instruction.next_offset() != end {
+            return None;
+        }
+
+        match instruction.op {
+            OpCode::OP_CONST | OpCode::OP_CONST_LONG => {
+                chunk.const_pool.values.get(instruction.operand?).cloned()
+            }
+            OpCode::OP_TRUE => Some(Value::Bool(true)),
+            OpCode::OP_FALSE => Some(Value::Bool(false)),
+            OpCode::OP_NIL => Some(Value::Nil),
+            _ => None,
+        }
+    }
+
+    /// Replaces the code from `start` to the end with the constant `value`.
+    fn replace_with_constant(&mut self, start: usize, value: Value, line: usize) -> EmitResult<()> {
+        self.chunk.truncate(start);
+        self.emit_value(value, line)
+    }
+
+    fn make_const(&mut self, value: Value) -> usize {
+        // Equal strings and numbers share a single slot of the constant pool.
+        let existing =
+            self.chunk
+                .const_pool
+                .values
+                .iter()
+                .position(|constant| match (constant, &value) {
+                    (Value::String(a), Value::String(b)) => a.string == b.string,
+                    (Value::Number(a), Value::Number(b)) => a.to_bits() == b.to_bits(),
+                    _ => false,
+                });
+
+        match existing {
+            Some(i) => i,
+            None => self.chunk.add_const(value),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::syntax::ast::{BinaryOp, UnaryOp};
+use crate::value::value::Value;
+use std::cmp::Ordering;
+
+/// Evaluates a unary operator on a constant operand as the VM would, or returns `None` if
+/// the VM would raise an error.
+pub fn fold_unary(op: UnaryOp, operand: &Value) -> Option<Value> {
+    match (op, operand) {
+        (UnaryOp::Negate, Value::Number(n)) => Some(Value::Number(-n)),
+        (UnaryOp::Not, value) => Some(Value::Bool(value.is_falsy())),
+        _ => None,
+    }
+}
+
+/// Evaluates a binary operator on constant operands as the VM would, or returns `None` if
+/// the VM would raise an error. Comparisons keep the negations the compiler emits for them,
+/// so `NaN >= 1` stays true.
+pub fn fold_binary(op: BinaryOp, left: &Value, right: &Value) -> Option<Value> {
+    let value = match (op, left, right) {
+        (BinaryOp::Equal, l, r) => Value::Bool(l.eq(r)),
+        (BinaryOp::NotEqual, l, r) => Value::Bool(!l.eq(r)),
+        (BinaryOp::Add, Value::String(l), Value::String(r)) => {
+            Value::from((String::from(l.string.as_str()) + r.string.as_str()).as_str())
+        }
+        (op, Value::Number(l), Value::Number(r)) => match op {
+            BinaryOp::Add => Value::Number(l + r),
+            BinaryOp::Subtract => Value::Number(l - r),
+            BinaryOp::Multiply => Value::Number(l * r),
+            BinaryOp::Divide => Value::Number(l / r),
+            BinaryOp::Greater => Value::Bool(l > r),
+            BinaryOp::GreaterEqual => Value::Bool(l.partial_cmp(r) != Some(Ordering::Less)),
+            BinaryOp::Less => Value::Bool(l < r),
+            BinaryOp::LessEqual => Value::Bool(l.partial_cmp(r) != Some(Ordering::Greater)),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fold_binary, fold_unary};
+    use crate::syntax::ast::{BinaryOp, UnaryOp};
+    use crate::value::value::Value;
+
+    #[test]
+    fn test_fold() {
+        let number = |n| Value::Number(n);
+
+        assert!(fold_binary(BinaryOp::Multiply, &number(2.0), &number(3.0))
+            .unwrap()
+            .eq(&number(6.0)));
+        assert!(fold_binary(BinaryOp::Divide, &number(1.0), &number(0.0))
+            .unwrap()
+            .eq(&number(f64::INFINITY)));
+        assert!(
+            fold_binary(BinaryOp::GreaterEqual, &number(f64::NAN), &number(1.0))
+                .unwrap()
+                .eq(&Value::Bool(true))
+        );
+        assert!(
+            fold_binary(BinaryOp::Add, &Value::from("a"), &Value::from("b"))
+                .unwrap()
+                .eq(&Value::from("ab"))
+        );
+        assert!(
+            fold_binary(BinaryOp::Equal, &Value::from("1"), &number(1.0))
+                .unwrap()
+                .eq(&Value::Bool(false))
+        );
+        assert!(fold_binary(BinaryOp::Subtract, &Value::from("a"), &number(1.0)).is_none());
+        assert!(fold_binary(BinaryOp::Add, &number(1.0), &Value::from("a")).is_none());
+
+        assert!(fold_unary(UnaryOp::Negate, &number(0.0))
+            .unwrap()
+            .to_string()
+            .eq("-0"));
+        assert!(fold_unary(UnaryOp::Not, &Value::from(""))
+            .unwrap()
+            .eq(&Value::Bool(true)));
+        assert!(fold_unary(UnaryOp::Negate, &Value::Nil).is_none());
+    }
+}
@@ -0,0 +1,488 @@
+use crate::compiler::scope::ScopeTracker;
+use crate::scanner::token::{Comment, Token, TokenType};
+use crate::syntax::ast::{BinaryOp, Expr, ExprKind, Identifier, Program, Stmt, StmtKind, UnaryOp};
+use crate::syntax::span::Span;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+const ALLOW: &str = "kentauri: allow(";
+
+/// A kind of warning, named like in `// kentauri: allow(unused_variable)`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Lint {
+    /// A local variable that is never read.
+    UnusedVariable,
+    /// A value stored in a local variable that is overwritten or goes out of scope before
+    /// it is read.
+    UnusedAssignment,
+    /// A local variable with the name of a variable of an enclosing scope.
+    Shadowing,
+    /// `==` or `!=` on operands that are never equal because their types differ.
+    ConstantComparison,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 4] = [
+        Lint::UnusedVariable,
+        Lint::UnusedAssignment,
+        Lint::Shadowing,
+        Lint::ConstantComparison,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "unused_variable",
+            Lint::UnusedAssignment => "unused_assignment",
+            Lint::Shadowing => "shadowing",
+            Lint::ConstantComparison => "constant_comparison",
+        }
+    }
+}
+
+impl FromStr for Lint {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Lint::ALL
+            .iter()
+            .find(|lint| lint.name() == name)
+            .copied()
+            .ok_or(format!("Unknown lint {}", name))
+    }
+}
+
+/// Which lints are reported, all of them unless allowed.
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    allowed: HashSet<Lint>,
+}
+
+impl LintConfig {
+    pub fn new() -> Self {
+        LintConfig::default()
+    }
+
+    pub fn allow(&mut self, lint: Lint) {
+        self.allowed.insert(lint);
+    }
+
+    pub fn is_enabled(&self, lint: Lint) -> bool {
+        !self.allowed.contains(&lint)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub lint: Lint,
+    pub message: String,
+    /// The source text the warning points at.
+    pub at: String,
+    pub span: Span,
+    pub line: usize,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "[line {}] Warning at '{}': {} ({})",
+            self.line,
+            self.at,
+            self.message,
+            self.lint.name()
+        )
+    }
+}
+
+/// Checks a parsed program for code that is legal but most likely a mistake. Expects a
+/// program that compiles, errors are not reported twice.
+///
+/// A `// kentauri: allow(name, ...)` comment silences the named lints on the line it ends,
+/// or on the next line if it is on a line of its own.
+pub fn lint(program: &Program, config: &LintConfig) -> Vec<Warning> {
+    let mut linter = Linter {
+        config,
+        allowed: allowed_lines(&program.comments),
+        scope: ScopeTracker::new(),
+        locals: Vec::new(),
+        globals: HashSet::new(),
+        warnings: Vec::new(),
+    };
+
+    for statement in program.statements.iter() {
+        linter.statement(statement);
+    }
+
+    let mut warnings = linter.warnings;
+    warnings.sort_by_key(|warning| warning.span.start);
+    warnings
+}
+
+/// The lints allowed on each line by comments.
+fn allowed_lines(comments: &[Comment]) -> HashMap<usize, Vec<String>> {
+    let mut allowed: HashMap<usize, Vec<String>> = HashMap::new();
+
+    for comment in comments {
+        let text = comment.text.trim_start_matches('/').trim();
+        let names = match text.strip_prefix(ALLOW).and_then(|t| t.strip_suffix(')')) {
+            Some(names) => names,
+            None => continue,
+        };
+
+        let line = if comment.trailing {
+            comment.line
+        } else {
+            comment.line + 1
+        };
+        allowed
+            .entry(line)
+            .or_default()
+            .extend(names.split(',').map(|name| name.trim().to_string()));
+    }
+
+    allowed
+}
+
+/// What is known about the uses of a local variable while its scope is open.
+struct LocalUse {
+    name: Identifier,
+    read: bool,
+    /// Assignments that were overwritten before being read.
+    dead: Vec<Identifier>,
+    /// The last assignment, until it is read.
+    pending: Option<Identifier>,
+}
+
+/// The type an expression has whenever it evaluates without an error.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum StaticType {
+    Number,
+    String,
+    Bool,
+    Nil,
+}
+
+impl Display for StaticType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            StaticType::Number => write!(f, "a number"),
+            StaticType::String => write!(f, "a string"),
+            StaticType::Bool => write!(f, "a boolean"),
+            StaticType::Nil => write!(f, "nil"),
+        }
+    }
+}
+
+struct Linter<'a> {
+    config: &'a LintConfig,
+    allowed: HashMap<usize, Vec<String>>,
+    scope: ScopeTracker,
+    // Indexed like the stack slots of the scope tracker.
+    locals: Vec<LocalUse>,
+    // Globals declared so far.
+    globals: HashSet<String>,
+    warnings: Vec<Warning>,
+}
+
+impl<'a> Linter<'a> {
+    fn warn(&mut self, lint: Lint, name: &Identifier, message: String) {
+        self.warn_at(lint, &name.name, name.span, name.line, message)
+    }
+
+    fn warn_at(&mut self, lint: Lint, at: &str, span: Span, line: usize, message: String) {
+        let allowed = self
+            .allowed
+            .get(&line)
+            .is_some_and(|names| names.iter().any(|name| name == lint.name()));
+        if allowed || !self.config.is_enabled(lint) {
+            return;
+        }
+
+        self.warnings.push(Warning {
+            lint,
+            message,
+            at: at.to_string(),
+            span,
+            line,
+        });
+    }
+
+    fn statement(&mut self, statement: &Stmt) {
+        match &statement.kind {
+            StmtKind::Var { name, initializer } => {
+                if let Some(initializer) = initializer {
+                    self.expression(initializer);
+                }
+
+                if self.scope.is_global() {
+                    self.globals.insert(name.name.clone());
+                } else {
+                    self.declare_local(name, initializer.is_some());
+                }
+            }
+            StmtKind::Print(expr) | StmtKind::Expression(expr) | StmtKind::Echo(expr) => {
+                self.expression(expr)
+            }
+            StmtKind::Block(statements) => {
+                self.scope.begin();
+                for statement in statements.iter() {
+                    self.statement(statement);
+                }
+
+                let count = self.scope.end() as usize;
+                for _ in 0..count {
+                    let local = self.locals.pop().unwrap();
+                    self.end_local(local);
+                }
+            }
+        }
+    }
+
+    fn declare_local(&mut self, name: &Identifier, initialized: bool) {
+        if self
+            .scope
+            .locate_local(|local| local == name.name)
+            .is_some()
+        {
+            self.warn(
+                Lint::Shadowing,
+                name,
+                format!("Local variable '{}' shadows an outer local", name.name),
+            );
+        } else if self.globals.contains(&name.name) {
+            self.warn(
+                Lint::Shadowing,
+                name,
+                format!("Local variable '{}' shadows a global", name.name),
+            );
+        }
+
+        let mut token = Token::new(TokenType::IDENTIFIER, name.name.clone(), name.line);
+        token.span = name.span;
+        // The compiler reports scopes with too many locals.
+        let _ = self.scope.add_local(token);
+        self.scope.define_last();
+
+        self.locals.push(LocalUse {
+            name: name.clone(),
+            read: false,
+            dead: Vec::new(),
+            pending: if initialized {
+                Some(name.clone())
+            } else {
+                None
+            },
+        });
+    }
+
+    fn end_local(&mut self, local: LocalUse) {
+        // Like in Rust, a leading underscore marks a variable as deliberately unused.
+        if local.name.name.starts_with('_') {
+            return;
+        }
+
+        if !local.read {
+            self.warn(
+                Lint::UnusedVariable,
+                &local.name,
+                format!("Local variable '{}' is never read", local.name.name),
+            );
+            return;
+        }
+
+        for assignment in local.dead.iter().chain(local.pending.iter()) {
+            self.warn(
+                Lint::UnusedAssignment,
+                assignment,
+                format!("Value assigned to '{}' is never read", assignment.name),
+            );
+        }
+    }
+
+    fn local(&mut self, name: &Identifier) -> Option<&mut LocalUse> {
+        let (slot, _) = self.scope.locate_local(|local| local == name.name)?;
+        self.locals.get_mut(slot)
+    }
+
+    fn expression(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Variable(name) => {
+                if let Some(local) = self.local(name) {
+                    local.read = true;
+                    local.pending = None;
+                }
+            }
+            ExprKind::Assign { name, value } => {
+                self.expression(value);
+
+                if let Some(local) = self.local(name) {
+                    if let Some(overwritten) = local.pending.replace(name.clone()) {
+                        local.dead.push(overwritten);
+                    }
+                }
+            }
+            ExprKind::Unary { operand, .. } => self.expression(operand),
+            ExprKind::Binary { op, left, right } => {
+                self.expression(left);
+                self.expression(right);
+
+                if let BinaryOp::Equal | BinaryOp::NotEqual = op {
+                    self.comparison(*op, expr, left, right);
+                }
+            }
+            ExprKind::Grouping(expr) => self.expression(expr),
+            ExprKind::Number(_)
+            | ExprKind::String(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Nil
+            | ExprKind::Invalid => (),
+        }
+    }
+
+    /// Warns about an equality of operands whose types differ, `Value::eq` is false for
+    /// those whatever the values.
+    fn comparison(&mut self, op: BinaryOp, expr: &Expr, left: &Expr, right: &Expr) {
+        let (left_type, right_type) = match (static_type(left), static_type(right)) {
+            (Some(l), Some(r)) if l != r => (l, r),
+            _ => return,
+        };
+
+        let result = op == BinaryOp::NotEqual;
+        self.warn_at(
+            Lint::ConstantComparison,
+            &op.to_string(),
+            expr.span,
+            expr.line,
+            format!(
+                "Comparison is always {}, {} is never equal to {}",
+                result, left_type, right_type
+            ),
+        );
+    }
+}
+
+fn static_type(expr: &Expr) -> Option<StaticType> {
+    match &expr.kind {
+        ExprKind::Number(_) => Some(StaticType::Number),
+        ExprKind::String(_) => Some(StaticType::String),
+        ExprKind::Bool(_) => Some(StaticType::Bool),
+        ExprKind::Nil => Some(StaticType::Nil),
+        ExprKind::Assign { value, .. } => static_type(value),
+        ExprKind::Grouping(expr) => static_type(expr),
+        ExprKind::Unary { op, .. } => match op {
+            UnaryOp::Negate => Some(StaticType::Number),
+            UnaryOp::Not => Some(StaticType::Bool),
+        },
+        ExprKind::Binary { op, left, right } => match op {
+            BinaryOp::Add => match (static_type(left), static_type(right)) {
+                (Some(StaticType::String), Some(StaticType::String)) => Some(StaticType::String),
+                (Some(StaticType::Number), Some(StaticType::Number)) => Some(StaticType::Number),
+                _ => None,
+            },
+            // A string times a number repeats the string.
+            BinaryOp::Multiply => match (static_type(left), static_type(right)) {
+                (Some(StaticType::String), _) => Some(StaticType::String),
+                (Some(StaticType::Number), Some(StaticType::Number)) => Some(StaticType::Number),
+                _ => None,
+            },
+            BinaryOp::Subtract | BinaryOp::Divide => Some(StaticType::Number),
+            _ => Some(StaticType::Bool),
+        },
+        ExprKind::Variable(_) | ExprKind::Invalid => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint, Lint, LintConfig};
+    use crate::compiler::compiler::Compiler;
+    use crate::syntax::parser::Parser;
+
+    fn warnings(source: &str, config: &LintConfig) -> Vec<String> {
+        // The linter only runs on programs that compile.
+        assert!(Compiler::new().compile(source).is_ok());
+        let mut parser = Parser::new(source);
+        parser.set_keep_comments(true);
+        let (program, errors) = parser.parse();
+        assert!(errors.is_empty());
+
+        lint(&program, config)
+            .iter()
+            .map(|warning| warning.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_variables() {
+        let source = "var g = 1;
+{
+    var a = 1;
+    var b = 2;
+    a = 3;
+    print a + b;
+    a = 4;
+    {
+        var b = g + 1;
+        var g;
+        var _unused;
+        print b;
+    }
+}";
+
+        assert_eq!(
+            vec![
+                "[line 3] Warning at 'a': Value assigned to 'a' is never read (unused_assignment)",
+                "[line 7] Warning at 'a': Value assigned to 'a' is never read (unused_assignment)",
+                "[line 9] Warning at 'b': Local variable 'b' shadows an outer local (shadowing)",
+                "[line 10] Warning at 'g': Local variable 'g' shadows a global (shadowing)",
+                "[line 10] Warning at 'g': Local variable 'g' is never read (unused_variable)",
+            ],
+            warnings(source, &LintConfig::new())
+        );
+    }
+
+    #[test]
+    fn test_constant_comparison() {
+        let source = "var a;
+print a == 1;
+print 1 == \"1\";
+print (a < 1) != nil;
+print -a == a + 1;
+print \"a\" + \"b\" == 2 * -a;
+print \"ab\" * 2 == \"abab\";
+print a * 2 == \"abab\";
+print \"ab\" * 2 == 4;";
+
+        assert_eq!(
+            vec![
+                "[line 3] Warning at '==': Comparison is always false, a number is never equal to a string (constant_comparison)",
+                "[line 4] Warning at '!=': Comparison is always true, a boolean is never equal to nil (constant_comparison)",
+                "[line 6] Warning at '==': Comparison is always false, a string is never equal to a number (constant_comparison)",
+                "[line 9] Warning at '==': Comparison is always false, a string is never equal to a number (constant_comparison)",
+            ],
+            warnings(source, &LintConfig::new())
+        );
+    }
+
+    #[test]
+    fn test_allow() {
+        let source = "{
+    // kentauri: allow(unused_variable)
+    var a;
+    var b; // kentauri: allow(shadowing, unused_variable)
+    var c;
+}";
+
+        assert_eq!(
+            vec!["[line 5] Warning at 'c': Local variable 'c' is never read (unused_variable)"],
+            warnings(source, &LintConfig::new())
+        );
+
+        let mut config = LintConfig::new();
+        config.allow(Lint::UnusedVariable);
+        assert!(warnings(source, &config).is_empty());
+        assert_eq!(Ok(Lint::Shadowing), "shadowing".parse());
+    }
+}
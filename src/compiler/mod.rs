@@ -1,4 +1,10 @@
+pub(crate) mod codegen;
 pub mod compilation;
 pub mod compiler;
-mod scope;
-mod precedence;
+mod emitter;
+mod fold;
+pub mod lint;
+pub mod peephole;
+pub(crate) mod precedence;
+pub(crate) mod scope;
+pub mod session;
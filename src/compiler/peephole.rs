@@ -0,0 +1,298 @@
+use crate::bytecode::chunk::{Chunk, LocalInfo};
+use crate::bytecode::instruction::{Instruction, Instructions};
+use crate::bytecode::opcode::OpCode;
+use crate::value::value::ValuePool;
+use num_enum::TryFromPrimitive;
+use std::iter;
+use std::mem;
+
+/// How much work the compiler spends on making the emitted code faster.
+#[repr(u8)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, PartialOrd, TryFromPrimitive)]
+pub enum OptLevel {
+    /// Emits the code exactly as parsed.
+    None,
+    /// Runs the peephole pass.
+    Peephole,
+    /// Also fuses common sequences into superinstructions.
+    #[default]
+    Superinstructions,
+}
+
+/// A superinstruction replacing the first `len` instructions of a sequence.
+struct Fused {
+    op: OpCode,
+    operands: [u8; 2],
+    len: usize,
+    /// The instruction of the sequence whose line the superinstruction takes, the one that
+    /// may raise a runtime error.
+    line_of: usize,
+}
+
+/// Rewrites short instruction sequences of a compiled chunk into cheaper ones: a comparison
+/// followed by `OP_NOT` becomes a single negated comparison, and a value pushed without
+/// side effects that is popped right away is dropped. Every remaining instruction keeps
+/// its line.
+///
+/// From `OptLevel::Superinstructions` on, sequences that are common in loop bodies are
+/// replaced by a single instruction as well, see `superinstruction`.
+///
+/// The offsets where locals start and end follow the code they point to. A local that
+/// starts inside a fused sequence starts after it, once its value is on the stack.
+pub fn optimize(mut chunk: Chunk, level: OptLevel) -> Chunk {
+    let lines: Vec<usize> = chunk
+        .line_code_index()
+        .iter()
+        .enumerate()
+        .flat_map(|(line, count)| iter::repeat_n(line, *count))
+        .collect();
+    let instructions: Vec<Instruction> = match Instructions::new(&chunk.code).collect() {
+        Ok(instructions) => instructions,
+        Err(_) => return chunk,
+    };
+
+    let const_pool = mem::replace(&mut chunk.const_pool, ValuePool::new());
+    let mut optimized = Chunk::from_parts(Vec::new(), const_pool, Vec::new());
+    // The new offset of each instruction.
+    let mut moved = vec![0; chunk.code.len() + 1];
+
+    let mut i = 0;
+    while i < instructions.len() {
+        let instruction = &instructions[i];
+        let line = lines[instruction.offset];
+        let start = optimized.code.len();
+
+        if level >= OptLevel::Superinstructions {
+            if let Some(fused) = superinstruction(&instructions[i..]) {
+                let line = lines[instructions[i + fused.line_of].offset];
+                optimized.write_code(fused.op, line);
+                for operand in fused.operands.iter() {
+                    optimized.write_byte(*operand, line);
+                }
+                move_group(
+                    &mut moved,
+                    &instructions[i..i + fused.len],
+                    start,
+                    &optimized,
+                );
+                i += fused.len;
+                continue;
+            }
+        }
+
+        let next = instructions.get(i + 1).map(|next| next.op);
+
+        let fused = match (instruction.op, next) {
+            (OpCode::OP_EQUAL, Some(OpCode::OP_NOT)) => Some(OpCode::OP_NOT_EQUAL),
+            (OpCode::OP_LESS, Some(OpCode::OP_NOT)) => Some(OpCode::OP_GREATER_EQUAL),
+            (OpCode::OP_GREATER, Some(OpCode::OP_NOT)) => Some(OpCode::OP_LESS_EQUAL),
+            (op, Some(OpCode::OP_POP)) if is_pure_push(op) => {
+                move_group(&mut moved, &instructions[i..i + 2], start, &optimized);
+                i += 2;
+                continue;
+            }
+            _ => None,
+        };
+
+        match fused {
+            Some(op) => {
+                optimized.write_code(op, line);
+                move_group(&mut moved, &instructions[i..i + 2], start, &optimized);
+                i += 2;
+            }
+            None => {
+                for byte in &chunk.code[instruction.offset..instruction.next_offset()] {
+                    optimized.write_byte(*byte, line);
+                }
+                moved[instruction.offset] = start;
+                i += 1;
+            }
+        }
+    }
+
+    moved[chunk.code.len()] = optimized.code.len();
+    optimized.locals = chunk
+        .locals
+        .drain(..)
+        .map(|local| LocalInfo {
+            start: moved[local.start],
+            end: moved[local.end],
+            ..local
+        })
+        .collect();
+
+    optimized
+}
+
+/// Records where a group of instructions replaced by the code written from `start` went:
+/// the first one to the start of the replacement and the others to its end.
+fn move_group(moved: &mut [usize], group: &[Instruction], start: usize, optimized: &Chunk) {
+    for (i, instruction) in group.iter().enumerate() {
+        moved[instruction.offset] = if i == 0 { start } else { optimized.code.len() };
+    }
+}
+
+/// Matches the start of `instructions` against the sequences that have a superinstruction:
+///
+/// - `GET_LOCAL s; CONST k; ADD; SET_LOCAL s; POP`, as in `i = i + 1;`, becomes
+///   `ADD_CONST_LOCAL k s`.
+/// - `GET_LOCAL a; GET_LOCAL b` becomes `GET_LOCALS a b`, unless the second value is
+///   popped right away.
+fn superinstruction(instructions: &[Instruction]) -> Option<Fused> {
+    let ops: Vec<OpCode> = instructions.iter().take(5).map(|i| i.op).collect();
+    let operand = |i: usize| instructions[i].operand.map(|operand| operand as u8);
+
+    match ops.as_slice() {
+        [OpCode::OP_GET_LOCAL, OpCode::OP_CONST, OpCode::OP_ADD, OpCode::OP_SET_LOCAL, OpCode::OP_POP]
+            if operand(0) == operand(3) =>
+        {
+            Some(Fused {
+                op: OpCode::OP_ADD_CONST_LOCAL,
+                operands: [operand(1)?, operand(0)?],
+                len: 5,
+                line_of: 2,
+            })
+        }
+        [OpCode::OP_GET_LOCAL, OpCode::OP_GET_LOCAL, next @ ..]
+            if next.first() != Some(&OpCode::OP_POP) =>
+        {
+            Some(Fused {
+                op: OpCode::OP_GET_LOCALS,
+                operands: [operand(0)?, operand(1)?],
+                len: 2,
+                line_of: 0,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Whether the instruction only pushes a value, without any effect or error.
+fn is_pure_push(op: OpCode) -> bool {
+    matches!(
+        op,
+        OpCode::OP_CONST
+            | OpCode::OP_CONST_LONG
+            | OpCode::OP_NIL
+            | OpCode::OP_TRUE
+            | OpCode::OP_FALSE
+            | OpCode::OP_GET_LOCAL
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{optimize, OptLevel};
+    use crate::bytecode::chunk::{Chunk, LocalInfo};
+    use crate::bytecode::opcode::OpCode;
+    use crate::value::value::Value;
+
+    #[test]
+    fn test_optimize() {
+        let mut chunk = Chunk::new();
+        chunk.add_const(Value::Number(1.0));
+        chunk.write_code(OpCode::OP_CONST, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_code(OpCode::OP_POP, 1);
+        chunk.write_code(OpCode::OP_TRUE, 2);
+        chunk.write_code(OpCode::OP_NIL, 2);
+        chunk.write_code(OpCode::OP_LESS, 2);
+        chunk.write_code(OpCode::OP_NOT, 2);
+        chunk.write_code(OpCode::OP_PRINT, 3);
+        chunk.write_code(OpCode::OP_GET_GLOBAL, 4);
+        chunk.write_byte(0, 4);
+        chunk.write_code(OpCode::OP_POP, 4);
+        chunk.write_code(OpCode::OP_RETURN, 5);
+
+        let optimized = optimize(chunk, OptLevel::Peephole);
+        assert_eq!(
+            vec![
+                OpCode::OP_TRUE as u8,
+                OpCode::OP_NIL as u8,
+                OpCode::OP_GREATER_EQUAL as u8,
+                OpCode::OP_PRINT as u8,
+                OpCode::OP_GET_GLOBAL as u8,
+                0,
+                OpCode::OP_POP as u8,
+                OpCode::OP_RETURN as u8
+            ],
+            optimized.code
+        );
+        assert_eq!(&[0, 0, 3, 1, 3, 1], optimized.line_code_index());
+        assert_eq!(1, optimized.const_pool.values.len());
+    }
+
+    #[test]
+    fn test_superinstructions() {
+        let mut chunk = Chunk::new();
+        chunk.add_const(Value::Number(1.0));
+        chunk.write_code(OpCode::OP_NIL, 1);
+        chunk.write_code(OpCode::OP_NIL, 1);
+        chunk.write_code(OpCode::OP_GET_LOCAL, 2);
+        chunk.write_byte(1, 2);
+        chunk.write_code(OpCode::OP_CONST, 2);
+        chunk.write_byte(0, 2);
+        chunk.write_code(OpCode::OP_ADD, 3);
+        chunk.write_code(OpCode::OP_SET_LOCAL, 3);
+        chunk.write_byte(1, 3);
+        chunk.write_code(OpCode::OP_POP, 3);
+        chunk.write_code(OpCode::OP_GET_LOCAL, 4);
+        chunk.write_byte(0, 4);
+        chunk.write_code(OpCode::OP_GET_LOCAL, 4);
+        chunk.write_byte(1, 4);
+        chunk.write_code(OpCode::OP_ADD, 4);
+        chunk.write_code(OpCode::OP_PRINT, 4);
+        chunk.write_code(OpCode::OP_RETURN, 5);
+
+        let optimized = optimize(chunk, OptLevel::Superinstructions);
+        assert_eq!(
+            vec![
+                OpCode::OP_NIL as u8,
+                OpCode::OP_NIL as u8,
+                OpCode::OP_ADD_CONST_LOCAL as u8,
+                0,
+                1,
+                OpCode::OP_GET_LOCALS as u8,
+                0,
+                1,
+                OpCode::OP_ADD as u8,
+                OpCode::OP_PRINT as u8,
+                OpCode::OP_RETURN as u8
+            ],
+            optimized.code
+        );
+        assert_eq!(&[0, 2, 0, 3, 5, 1], optimized.line_code_index());
+    }
+
+    #[test]
+    fn test_moves_locals() {
+        // { var a = 1; var b = a; print b; }
+        let mut chunk = Chunk::new();
+        chunk.add_const(Value::Number(1.0));
+        chunk.write_code(OpCode::OP_CONST, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_code(OpCode::OP_GET_LOCAL, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_code(OpCode::OP_GET_LOCAL, 1);
+        chunk.write_byte(1, 1);
+        chunk.write_code(OpCode::OP_PRINT, 1);
+        chunk.write_code(OpCode::OP_POP, 1);
+        chunk.write_code(OpCode::OP_POP, 1);
+        chunk.write_code(OpCode::OP_RETURN, 1);
+        let local = |name: &str, slot, start, end| LocalInfo {
+            name: name.to_string(),
+            slot,
+            start,
+            end,
+        };
+        chunk.locals = vec![local("a", 0, 2, 7), local("b", 1, 4, 7)];
+
+        let optimized = optimize(chunk, OptLevel::Superinstructions);
+        assert_eq!(OpCode::OP_GET_LOCALS as u8, optimized.code[2]);
+        // The value of b is on the stack only once the fused instruction ran.
+        assert_eq!(
+            vec![local("a", 0, 2, 6), local("b", 1, 5, 6)],
+            optimized.locals
+        );
+    }
+}
@@ -1,9 +1,7 @@
 use crate::scanner::token::TokenType;
+use lazy_static::lazy_static;
 use num_enum::TryFromPrimitive;
 use std::convert::TryFrom;
-#[macro_use]
-use lazy_static::lazy_static;
-use std::borrow::Borrow;
 
 lazy_static! {
     static ref RULES: Vec<ParseRule> = {
@@ -56,6 +54,7 @@ lazy_static! {
 
 #[derive(TryFromPrimitive, Copy, Clone, Debug)]
 #[repr(u8)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Precedence {
     NONE = 0,
     ASSIGNMENT,
@@ -96,9 +95,7 @@ impl ParseRule {
     }
 
     pub fn get_incremented_prec(&self, add: u8) -> Option<Precedence> {
-        let new_prec = Precedence::try_from(self.precedence as u8 + add).ok();
-
-        new_prec
+        Precedence::try_from(self.precedence as u8 + add).ok()
     }
 }
 
@@ -2,7 +2,6 @@ use crate::scanner::token::Token;
 
 pub struct ScopeTracker {
     locals: Vec<Local>,
-    local_count: usize,
     scope_depth: i64,
 }
 
@@ -10,7 +9,6 @@ impl ScopeTracker {
     pub fn new() -> Self {
         ScopeTracker {
             locals: Vec::new(),
-            local_count: 0,
             scope_depth: 0,
         }
     }
@@ -23,9 +21,9 @@ impl ScopeTracker {
         self.scope_depth -= 1;
         let mut pop_count = 0;
 
-        while self.local_count > 0 && self.locals.last().as_ref().unwrap().depth > self.scope_depth {
+        while !self.locals.is_empty() && self.locals.last().unwrap().depth > self.scope_depth {
             pop_count += 1;
-            self.local_count -= 1;
+            self.locals.pop();
         }
 
         pop_count
@@ -35,33 +33,48 @@ impl ScopeTracker {
         self.scope_depth == 0
     }
 
-    pub fn add_local(&mut self, name: Token) {
-        self.local_count += 1;
-        self.locals.push(Local {
-            name,
-            depth: -1
-        });
+    /// Declares a local, failing when its stack slot does not fit in an operand byte.
+    pub fn add_local(&mut self, name: Token) -> Result<(), &'static str> {
+        self.locals.push(Local { name, depth: -1 });
+        if self.locals.len() > usize::from(u8::MAX) + 1 {
+            return Err("Too many local variables in scope");
+        }
+
+        Ok(())
     }
 
-    pub fn define_last(&mut self) {
+    /// Marks the last local as initialized and returns its stack slot.
+    pub fn define_last(&mut self) -> usize {
         self.locals.last_mut().unwrap().depth = self.scope_depth;
+        self.locals.len() - 1
+    }
+
+    pub fn name_of(&self, slot: usize) -> &str {
+        &self.locals[slot].name.lexem
     }
 }
 
-impl ScopeTracker
+impl ScopeTracker {
+    /// Finds the innermost local matching `f` and returns its stack slot and whether its
+    /// initializer has already been compiled.
+    pub fn locate_local<F>(&self, f: F) -> Option<(usize, bool)>
+    where
+        F: Fn(&str) -> bool,
     {
-    pub fn locate_local<F>(&self, f: F) -> i64 where F: Fn(&str) -> bool {
-        for local in self.locals.iter().rev() {
-            if local.depth != -1 && local.depth < self.scope_depth {
-                return -1;
-            }
-
-            if f(&local.name.lexem) {
-                return local.depth;
-            }
-        }
+        self.locals
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, local)| f(&local.name.lexem))
+            .map(|(slot, local)| (slot, local.depth != -1))
+    }
 
-        -1
+    pub fn is_declared_in_current(&self, name: &str) -> bool {
+        self.locals
+            .iter()
+            .rev()
+            .take_while(|local| local.depth == -1 || local.depth >= self.scope_depth)
+            .any(|local| local.name.lexem == name)
     }
 }
 
@@ -0,0 +1,78 @@
+use crate::bytecode::chunk::Chunk;
+use crate::compiler::compilation::Compilation;
+use crate::compiler::compiler::Compiler;
+use crate::interpreter::interpreter::InterpreterResult;
+
+/// Incremental compilation for interactive use. Every input compiles into its own chunk,
+/// while the definitions of all inputs so far stay in one `Compilation`. Constants are
+/// not shared: each chunk has its own pool, deduplicated only within that input.
+/// An input that fails to compile leaves the session untouched.
+pub struct Session {
+    compiler: Compiler,
+    compilation: Compilation,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session {
+            compiler: Compiler::new(),
+            compilation: Compilation::new(),
+        }
+    }
+
+    pub fn compiler(&mut self) -> &mut Compiler {
+        &mut self.compiler
+    }
+
+    pub fn compilation(&self) -> &Compilation {
+        &self.compilation
+    }
+
+    pub fn compile(&mut self, source: &str) -> InterpreterResult<Chunk> {
+        self.compiler.compile_into(source, &mut self.compilation)
+    }
+
+    /// Forgets every earlier input.
+    pub fn reset(&mut self) {
+        self.compilation = Compilation::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Session;
+    use crate::output::sink::OutputBuffer;
+
+    #[test]
+    fn test_definitions_across_inputs() {
+        let mut session = Session::new();
+        session
+            .compiler()
+            .set_diagnostics(OutputBuffer::new().sink());
+
+        assert!(session.compile("var a = 1; var b = \"x\";").is_ok());
+        assert!(session.compile("{ var local = 2; }").is_ok());
+        assert!(session.compile("var c = ;").is_err());
+        assert!(session.compile("var a = \"x\";").is_ok());
+
+        let compilation = session.compilation();
+        assert_eq!(3, compilation.inputs);
+        assert_eq!(2, compilation.definitions.len());
+        assert_eq!(2, compilation.definition("a").unwrap().input);
+        assert_eq!(0, compilation.definition("b").unwrap().input);
+        assert!(compilation.definition("c").is_none());
+
+        session.reset();
+        assert!(session.compilation().definition("a").is_none());
+    }
+
+    #[test]
+    fn test_deduplicates_constants() {
+        let mut session = Session::new();
+        let chunk = session
+            .compile("var a = 1; a = a + 1; print \"a\";")
+            .unwrap();
+
+        assert_eq!(2, chunk.const_pool.values.len());
+    }
+}
@@ -0,0 +1,421 @@
+use crate::bytecode::chunk::Chunk;
+use crate::compiler::peephole::OptLevel;
+use crate::dap::channel::{Channel, OutputEvents};
+use crate::debug::debugger::{globals, locals, Debugger, Resume, Stop};
+use crate::interpreter::interpreter::{read_input, Interpreter};
+use crate::output::sink::Sink;
+use crate::util::json::Json;
+use crate::util::rpc::read_message;
+use crate::value::value::Value;
+use crate::vm::hook::DebugHook;
+use crate::vm::vm::VM;
+use std::io;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+// Scripts run on a single thread in a single frame.
+const THREAD_ID: usize = 1;
+const FRAME_ID: usize = 0;
+const LOCALS_REFERENCE: usize = 1;
+const GLOBALS_REFERENCE: usize = 2;
+
+// Requests that need the script to be stopped.
+const STOPPED_COMMANDS: [&str; 7] = [
+    "stackTrace",
+    "scopes",
+    "variables",
+    "continue",
+    "next",
+    "stepIn",
+    "stepOut",
+];
+
+/// Serves the requests read from `reader` until the client disconnects, and returns the
+/// exit code: 0 after a disconnect request, 1 when the input ends first.
+pub fn run<R: BufRead, W: Write + 'static>(reader: R, writer: W) -> io::Result<i32> {
+    Adapter::new(reader, Sink::new(writer)).serve()
+}
+
+enum Flow {
+    Stay,
+    Start,
+    Resume(Resume),
+    Disconnect,
+}
+
+/// Debug adapter running one script, launched by a client, under a `Debugger`. The
+/// script starts once the client is done configuring it, and its output is sent as
+/// output events. While it is stopped, requests are served from within the VM hook.
+pub struct Adapter<R: BufRead> {
+    reader: R,
+    channel: Channel,
+    // Sinks of the script output and errors.
+    output: Sink,
+    diagnostics: Sink,
+    // Lines and columns as the client counts them, from 1 unless it says otherwise.
+    first_line: usize,
+    first_column: usize,
+    path: Option<String>,
+    // The launched script until it starts.
+    program: Option<(Interpreter, Chunk)>,
+    // `None` when running without debugging.
+    debugger: Option<Debugger>,
+    // Set when the client disconnects, or the input ends, while the script runs.
+    exit: Option<i32>,
+    error: Option<io::Error>,
+}
+
+impl<R: BufRead> Adapter<R> {
+    pub fn new(reader: R, writer: Sink) -> Self {
+        let channel = Channel::new(writer);
+
+        Adapter {
+            reader,
+            output: Sink::new(OutputEvents::new(channel.clone(), "stdout")),
+            diagnostics: Sink::new(OutputEvents::new(channel.clone(), "stderr")),
+            channel,
+            first_line: 1,
+            first_column: 1,
+            path: None,
+            program: None,
+            debugger: None,
+            exit: None,
+            error: None,
+        }
+    }
+
+    /// Serves requests until the client disconnects, running the script when asked to.
+    pub fn serve(&mut self) -> io::Result<i32> {
+        while let Some(message) = read_message(&mut self.reader)? {
+            // Requests that are not JSON have no sequence number to answer.
+            let message = match message {
+                Ok(message) => message,
+                Err(_) => continue,
+            };
+
+            match self.handle(None, &message)? {
+                Flow::Start => {
+                    self.start()?;
+                    if let Some(code) = self.exit {
+                        return Ok(code);
+                    }
+                }
+                Flow::Disconnect => return Ok(0),
+                Flow::Stay | Flow::Resume(_) => (),
+            }
+        }
+
+        Ok(1)
+    }
+
+    /// Answers a request, given the VM when the script is stopped.
+    fn handle(&mut self, vm: Option<&VM>, message: &Json) -> io::Result<Flow> {
+        if message.get("type").as_str() != Some("request") {
+            return Ok(Flow::Stay);
+        }
+        let command = message.get("command").as_str().unwrap_or_default();
+        let arguments = message.get("arguments");
+
+        let mut flow = Flow::Stay;
+        let response = match (command, vm) {
+            ("initialize", _) => Ok(self.initialize(arguments)),
+            ("launch", _) => self.launch(arguments),
+            ("setBreakpoints", _) => self.set_breakpoints(arguments),
+            ("configurationDone", _) => match (&self.path, &self.program) {
+                (None, _) => Err(String::from("No program was launched")),
+                (Some(_), None) => Err(String::from("The program already started")),
+                (Some(_), Some(_)) => {
+                    flow = Flow::Start;
+                    Ok(Json::Null)
+                }
+            },
+            ("threads", _) => Ok(Json::object(vec![(
+                "threads",
+                Json::from(vec![Json::object(vec![
+                    ("id", Json::from(THREAD_ID)),
+                    ("name", Json::from("main")),
+                ])]),
+            )])),
+            ("disconnect", _) => {
+                flow = Flow::Disconnect;
+                Ok(Json::Null)
+            }
+            (_, None) if STOPPED_COMMANDS.contains(&command) => {
+                Err(String::from("The program is not stopped"))
+            }
+            ("stackTrace", Some(vm)) => Ok(self.stack_trace(vm)),
+            ("scopes", Some(_)) => Ok(scopes()),
+            ("variables", Some(vm)) => variables(vm, arguments),
+            ("continue", Some(_)) => {
+                flow = Flow::Resume(Resume::Continue);
+                Ok(Json::object(vec![(
+                    "allThreadsContinued",
+                    Json::from(true),
+                )]))
+            }
+            ("next", Some(_)) => {
+                flow = Flow::Resume(Resume::StepOver);
+                Ok(Json::Null)
+            }
+            ("stepIn", Some(_)) => {
+                flow = Flow::Resume(Resume::StepIn);
+                Ok(Json::Null)
+            }
+            ("stepOut", Some(_)) => {
+                flow = Flow::Resume(Resume::StepOut);
+                Ok(Json::Null)
+            }
+            _ => Err(format!("Unknown command {}", command)),
+        };
+
+        // Configuration requests are only accepted once there is a program to configure.
+        let launched = command == "launch" && response.is_ok();
+        self.channel.respond(message, response)?;
+        if launched {
+            self.channel.event("initialized", Json::Null)?;
+        }
+
+        Ok(flow)
+    }
+
+    fn initialize(&mut self, arguments: &Json) -> Json {
+        let first = |key: &str| match arguments.get(key).as_bool() {
+            Some(false) => 0,
+            _ => 1,
+        };
+        self.first_line = first("linesStartAt1");
+        self.first_column = first("columnsStartAt1");
+
+        Json::object(vec![("supportsConfigurationDoneRequest", Json::from(true))])
+    }
+
+    /// Compiles the program, unoptimised like `kentauri debug` does, to run it once the
+    /// client is done configuring it.
+    fn launch(&mut self, arguments: &Json) -> Result<Json, String> {
+        if self.path.is_some() {
+            return Err(String::from("A program was already launched"));
+        }
+        let path = arguments
+            .get("program")
+            .as_str()
+            .ok_or_else(|| String::from("Missing program"))?;
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_opt_level(OptLevel::None);
+        interpreter.set_output(self.output.clone());
+        interpreter.set_diagnostics(self.diagnostics.clone());
+
+        let chunk = read_input(path)
+            .and_then(|bytes| interpreter.load(&bytes))
+            .map_err(|e| e.to_string())?;
+
+        let mut debugger = Debugger::new(&chunk);
+        if !arguments.get("stopOnEntry").as_bool().unwrap_or_default() {
+            debugger.resume(Resume::Continue);
+        }
+        if !arguments.get("noDebug").as_bool().unwrap_or_default() {
+            self.debugger = Some(debugger);
+        }
+        self.path = Some(path.to_string());
+        self.program = Some((interpreter, chunk));
+
+        Ok(Json::Null)
+    }
+
+    /// Replaces the breakpoints, moving each to the first line from its own that has code.
+    fn set_breakpoints(&mut self, arguments: &Json) -> Result<Json, String> {
+        if self.path.is_none() {
+            return Err(String::from("No program was launched"));
+        }
+        let lines: Vec<usize> = arguments
+            .get("breakpoints")
+            .as_array()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|breakpoint| breakpoint.get("line").as_usize())
+            .collect();
+
+        let (first_line, debugger) = (self.first_line, &mut self.debugger);
+        if let Some(debugger) = debugger.as_mut() {
+            debugger.clear_breakpoints();
+        }
+        let breakpoints = lines
+            .into_iter()
+            .map(|requested| {
+                let line = (requested + 1).saturating_sub(first_line);
+                match debugger.as_mut().map(|d| d.set_breakpoint(line)) {
+                    Some(Some(line)) => Json::object(vec![
+                        ("verified", Json::from(true)),
+                        ("line", Json::from(line + first_line - 1)),
+                    ]),
+                    Some(None) => unverified(requested, "No code on or after this line"),
+                    None => unverified(requested, "Running without debugging"),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Json::object(vec![("breakpoints", Json::from(breakpoints))]))
+    }
+
+    fn stack_trace(&self, vm: &VM) -> Json {
+        let path = self.path.as_deref().unwrap_or_default();
+        let name = Path::new(path)
+            .file_name()
+            .map_or(path.into(), |name| name.to_string_lossy());
+        let line = self.debugger.as_ref().map_or(0, |d| d.line_at(vm.ip()));
+
+        let frame = Json::object(vec![
+            ("id", Json::from(FRAME_ID)),
+            ("name", Json::from("<script>")),
+            (
+                "source",
+                Json::object(vec![
+                    ("name", Json::from(name.as_ref())),
+                    ("path", Json::from(path)),
+                ]),
+            ),
+            ("line", Json::from(line + self.first_line - 1)),
+            ("column", Json::from(self.first_column)),
+        ]);
+
+        Json::object(vec![
+            ("stackFrames", Json::from(vec![frame])),
+            ("totalFrames", Json::from(1)),
+        ])
+    }
+
+    /// Runs the launched script to its end, unless the client disconnects first.
+    fn start(&mut self) -> io::Result<()> {
+        let (mut interpreter, chunk) = match self.program.take() {
+            Some(program) => program,
+            None => return Ok(()),
+        };
+
+        let result = interpreter.execute_with(chunk, self);
+        self.output.flush()?;
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        if self.exit.is_some() {
+            return Ok(());
+        }
+
+        let exit_code = match result {
+            Ok(()) => 0,
+            Err(e) => {
+                interpreter.report(&e);
+                self.diagnostics.flush()?;
+                e.exit_code()
+            }
+        };
+        self.channel.event(
+            "exited",
+            Json::object(vec![("exitCode", Json::Number(exit_code as f64))]),
+        )?;
+        self.channel.event("terminated", Json::Null)
+    }
+
+    /// Tells the client the script stopped and serves its requests until one resumes the
+    /// script. Returns `false` to end it.
+    fn stopped(&mut self, vm: &VM, stop: Stop) -> io::Result<bool> {
+        self.output.flush()?;
+        let reason = match stop {
+            Stop::Entry => "entry",
+            Stop::Breakpoint => "breakpoint",
+            Stop::Step => "step",
+        };
+        self.channel.event(
+            "stopped",
+            Json::object(vec![
+                ("reason", Json::from(reason)),
+                ("threadId", Json::from(THREAD_ID)),
+                ("allThreadsStopped", Json::from(true)),
+            ]),
+        )?;
+
+        while let Some(message) = read_message(&mut self.reader)? {
+            let message = match message {
+                Ok(message) => message,
+                Err(_) => continue,
+            };
+
+            match self.handle(Some(vm), &message)? {
+                Flow::Resume(resume) => {
+                    if let Some(debugger) = self.debugger.as_mut() {
+                        debugger.resume(resume);
+                    }
+                    return Ok(true);
+                }
+                Flow::Disconnect => {
+                    self.exit = Some(0);
+                    return Ok(false);
+                }
+                Flow::Stay | Flow::Start => (),
+            }
+        }
+
+        self.exit = Some(1);
+        Ok(false)
+    }
+}
+
+impl<R: BufRead> DebugHook for Adapter<R> {
+    fn before_instruction(&mut self, vm: &VM) -> bool {
+        let stop = match self.debugger.as_mut().and_then(|d| d.check(vm.ip())) {
+            Some(stop) => stop,
+            None => return true,
+        };
+
+        self.stopped(vm, stop).unwrap_or_else(|e| {
+            self.error = Some(e);
+            false
+        })
+    }
+}
+
+fn unverified(line: usize, message: &str) -> Json {
+    Json::object(vec![
+        ("verified", Json::from(false)),
+        ("line", Json::from(line)),
+        ("message", Json::from(message)),
+    ])
+}
+
+fn scopes() -> Json {
+    let scope = |name: &str, reference: usize| {
+        Json::object(vec![
+            ("name", Json::from(name)),
+            ("variablesReference", Json::from(reference)),
+            ("expensive", Json::from(false)),
+        ])
+    };
+
+    Json::object(vec![(
+        "scopes",
+        Json::from(vec![
+            scope("Locals", LOCALS_REFERENCE),
+            scope("Globals", GLOBALS_REFERENCE),
+        ]),
+    )])
+}
+
+fn variables(vm: &VM, arguments: &Json) -> Result<Json, String> {
+    let variables: Vec<(String, Value)> = match arguments.get("variablesReference").as_usize() {
+        Some(LOCALS_REFERENCE) => locals(vm),
+        Some(GLOBALS_REFERENCE) => globals(vm),
+        _ => return Err(String::from("Unknown variables reference")),
+    };
+
+    let variables = variables
+        .into_iter()
+        .map(|(name, value)| {
+            Json::object(vec![
+                ("name", Json::from(name)),
+                ("value", Json::from(value.repr())),
+                ("variablesReference", Json::from(0)),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Json::object(vec![("variables", Json::from(variables))]))
+}
@@ -0,0 +1,155 @@
+use crate::output::sink::Sink;
+use crate::util::json::Json;
+use crate::util::rpc::write_message;
+use std::cell::Cell;
+use std::io;
+use std::io::Write;
+use std::rc::Rc;
+
+/// Writes the responses and events of the debug adapter, numbering them in the order they
+/// are sent. Clones share the writer and the numbering.
+#[derive(Clone)]
+pub struct Channel {
+    writer: Sink,
+    seq: Rc<Cell<usize>>,
+}
+
+impl Channel {
+    pub fn new(writer: Sink) -> Self {
+        Channel {
+            writer,
+            seq: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// Answers `request` with a body, or with the message of its failure. A null body is
+    /// left out.
+    pub fn respond(&mut self, request: &Json, response: Result<Json, String>) -> io::Result<()> {
+        let mut members = vec![
+            ("request_seq", request.get("seq").clone()),
+            ("success", Json::from(response.is_ok())),
+            ("command", request.get("command").clone()),
+        ];
+        match response {
+            Ok(Json::Null) => (),
+            Ok(body) => members.push(("body", body)),
+            Err(message) => members.push(("message", Json::from(message))),
+        }
+
+        self.send("response", members)
+    }
+
+    /// Sends `event`, without a body when it is null.
+    pub fn event(&mut self, event: &str, body: Json) -> io::Result<()> {
+        let mut members = vec![("event", Json::from(event))];
+        if !body.is_null() {
+            members.push(("body", body));
+        }
+
+        self.send("event", members)
+    }
+
+    fn send(&mut self, kind: &str, members: Vec<(&str, Json)>) -> io::Result<()> {
+        let seq = self.seq.get() + 1;
+        self.seq.set(seq);
+
+        let mut message = vec![("seq", Json::from(seq)), ("type", Json::from(kind))];
+        message.extend(members);
+        write_message(&mut self.writer, &Json::object(message))
+    }
+}
+
+/// Sends what is written to it as output events of a category, such as `stdout`, one
+/// event per line. Flushing sends an unfinished line.
+pub struct OutputEvents {
+    channel: Channel,
+    category: &'static str,
+    line: Vec<u8>,
+}
+
+impl OutputEvents {
+    pub fn new(channel: Channel, category: &'static str) -> Self {
+        OutputEvents {
+            channel,
+            category,
+            line: Vec::new(),
+        }
+    }
+
+    fn send(&mut self, end: usize) -> io::Result<()> {
+        let text: Vec<u8> = self.line.drain(..end).collect();
+        self.channel.event(
+            "output",
+            Json::object(vec![
+                ("category", Json::from(self.category)),
+                (
+                    "output",
+                    Json::from(String::from_utf8_lossy(&text).into_owned()),
+                ),
+            ]),
+        )
+    }
+}
+
+impl Write for OutputEvents {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.line.extend_from_slice(buf);
+        while let Some(i) = self.line.iter().position(|b| *b == b'\n') {
+            self.send(i + 1)?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.line.is_empty() {
+            self.send(self.line.len())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Channel, OutputEvents};
+    use crate::output::sink::{OutputBuffer, Sink};
+    use crate::util::json::Json;
+    use crate::util::rpc::read_message;
+    use std::io::{Cursor, Write};
+
+    #[test]
+    fn test_output_lines() {
+        let buffer = OutputBuffer::new();
+        let channel = Channel::new(buffer.sink());
+        let mut output = Sink::new(OutputEvents::new(channel.clone(), "stdout"));
+
+        write!(output, "a").unwrap();
+        writeln!(output, "b\nc").unwrap();
+        write!(output, "d").unwrap();
+        channel
+            .clone()
+            .respond(&Json::object(vec![("seq", Json::from(4))]), Ok(Json::Null))
+            .unwrap();
+        output.flush().unwrap();
+        output.flush().unwrap();
+
+        let mut reader = Cursor::new(buffer.take());
+        let mut messages = Vec::new();
+        while let Some(message) = read_message(&mut reader).unwrap() {
+            messages.push(message.unwrap());
+        }
+
+        let seqs: Vec<usize> = messages
+            .iter()
+            .map(|m| m.get("seq").as_usize().unwrap())
+            .collect();
+        assert_eq!(vec![1, 2, 3, 4], seqs);
+        let text = |i: usize| messages[i].get("body").get("output").as_str();
+        assert_eq!(Some("ab\n"), text(0));
+        assert_eq!(Some("c\n"), text(1));
+        assert_eq!(Some(4), messages[2].get("request_seq").as_usize());
+        assert!(messages[2].get("body").is_null());
+        assert_eq!(Some("d"), text(3));
+    }
+}
@@ -0,0 +1,309 @@
+use crate::bytecode::chunk::Chunk;
+use crate::debug::debugger::{globals, locals, lookup, Debugger, Resume, Stop};
+use crate::output::sink::Sink;
+use crate::value::value::Value;
+use crate::vm::hook::DebugHook;
+use crate::vm::vm::VM;
+use std::io::{BufRead, Write};
+
+const PROMPT: &str = "(debug) ";
+
+// Lines shown by `list` on each side of the current one.
+const LIST_CONTEXT: usize = 2;
+
+const HELP: &str = "break <line>    Stop before running <line>, b for short
+delete <line>   Remove the breakpoint on <line>, d for short
+breakpoints     List the breakpoints
+step            Run to the next line, s for short
+next            Run to the next line, stepping over calls, n for short
+out             Run until the current call returns, o for short
+continue        Run until the next breakpoint, c for short
+print <name>    Show the value of a variable, p for short
+locals          List the local variables in scope
+globals         List the global variables
+stack           Show the value stack, from the bottom
+list            Show the source around the current line, l for short
+help            Show this message
+quit            Stop the script, same as Ctrl-D
+
+An empty line repeats the last command.";
+
+enum Flow {
+    Stay,
+    Resume(Resume),
+    Quit,
+}
+
+/// Interactive debugger reading commands from an input, stopping before the first line of
+/// the script. Quitting interrupts the execution, see `has_quit`.
+pub struct Console<R: BufRead> {
+    debugger: Debugger,
+    input: R,
+    output: Sink,
+    source: Vec<String>,
+    last_command: String,
+    quit: bool,
+}
+
+impl<R: BufRead> Console<R> {
+    pub fn new(chunk: &Chunk, input: R) -> Self {
+        Console {
+            debugger: Debugger::new(chunk),
+            input,
+            output: Sink::stdout(),
+            source: Vec::new(),
+            last_command: String::new(),
+            quit: false,
+        }
+    }
+
+    /// Redirects prompts and the output of commands.
+    pub fn set_output(&mut self, output: Sink) {
+        self.output = output;
+    }
+
+    /// Sets the source of the chunk, to show its lines.
+    pub fn set_source(&mut self, source: &str) {
+        self.source = source.lines().map(String::from).collect();
+    }
+
+    /// Whether the execution stopped because of `quit` rather than an interrupt.
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Reads and runs commands until one resumes the execution. Returns `false` to quit.
+    fn stopped(&mut self, vm: &VM, stop: Stop) -> bool {
+        let line = self.debugger.line_at(vm.ip());
+        let reason = match stop {
+            Stop::Entry => "entry",
+            Stop::Breakpoint => "breakpoint",
+            Stop::Step => "step",
+        };
+        let _ = writeln!(self.output, "Stopped at line {} ({})", line, reason);
+        self.show_line(line, "");
+
+        loop {
+            let _ = write!(self.output, "{}", PROMPT);
+            let _ = self.output.flush();
+
+            let mut entry = String::new();
+            match self.input.read_line(&mut entry) {
+                Ok(0) | Err(_) => {
+                    let _ = writeln!(self.output);
+                    self.quit = true;
+                    return false;
+                }
+                Ok(_) => (),
+            }
+
+            let entry = match entry.trim() {
+                "" => self.last_command.clone(),
+                entry => entry.to_string(),
+            };
+            self.last_command = entry.clone();
+
+            match self.command(vm, &entry) {
+                Flow::Stay => (),
+                Flow::Resume(resume) => {
+                    self.debugger.resume(resume);
+                    return true;
+                }
+                Flow::Quit => {
+                    self.quit = true;
+                    return false;
+                }
+            }
+        }
+    }
+
+    fn command(&mut self, vm: &VM, entry: &str) -> Flow {
+        let (name, argument) = match entry.find(char::is_whitespace) {
+            Some(i) => (&entry[..i], entry[i..].trim()),
+            None => (entry, ""),
+        };
+
+        match name {
+            "step" | "s" => return Flow::Resume(Resume::StepIn),
+            "next" | "n" => return Flow::Resume(Resume::StepOver),
+            "out" | "o" => return Flow::Resume(Resume::StepOut),
+            "continue" | "c" => return Flow::Resume(Resume::Continue),
+            "quit" | "q" => return Flow::Quit,
+            "break" | "b" => match argument.parse::<usize>() {
+                Ok(line) => match self.debugger.set_breakpoint(line) {
+                    Some(line) => self.print(&format!("Breakpoint on line {}", line)),
+                    None => self.print(&format!("No code on or after line {}", line)),
+                },
+                Err(_) => self.print("Usage: break <line>"),
+            },
+            "delete" | "d" => match argument.parse::<usize>() {
+                Ok(line) if self.debugger.clear_breakpoint(line) => {
+                    self.print(&format!("Deleted the breakpoint on line {}", line))
+                }
+                Ok(line) => self.print(&format!("No breakpoint on line {}", line)),
+                Err(_) => self.print("Usage: delete <line>"),
+            },
+            "breakpoints" => {
+                let lines: Vec<String> =
+                    self.debugger.breakpoints().map(|l| l.to_string()).collect();
+                if lines.is_empty() {
+                    self.print("No breakpoints");
+                } else {
+                    self.print(&format!("Breakpoints on lines {}", lines.join(", ")));
+                }
+            }
+            "print" | "p" if !argument.is_empty() => match lookup(vm, argument) {
+                Some(value) => self.print(&format!("{} = {}", argument, value.repr())),
+                None => self.print(&format!("No variable named {}", argument)),
+            },
+            "locals" => self.print_variables(locals(vm), "No locals in scope"),
+            "globals" => self.print_variables(globals(vm), "No globals"),
+            "stack" => {
+                if vm.stack().is_empty() {
+                    self.print("The stack is empty");
+                }
+                for (slot, value) in vm.stack().iter().enumerate() {
+                    self.print(&format!("[{}] {}", slot, value.repr()));
+                }
+            }
+            "list" | "l" => {
+                let line = self.debugger.line_at(vm.ip());
+                if self.source.is_empty() {
+                    self.print("No source for this script");
+                }
+                for shown in line.saturating_sub(LIST_CONTEXT).max(1)..=line + LIST_CONTEXT {
+                    let marker = if shown == line { "->" } else { "" };
+                    self.show_line(shown, marker);
+                }
+            }
+            "help" | "h" => self.print(HELP),
+            _ => self.print(&format!(
+                "Unknown command {}, help lists the commands",
+                entry
+            )),
+        }
+
+        Flow::Stay
+    }
+
+    fn print(&mut self, text: &str) {
+        let _ = writeln!(self.output, "{}", text);
+    }
+
+    fn print_variables(&mut self, variables: Vec<(String, Value)>, none: &str) {
+        if variables.is_empty() {
+            self.print(none);
+        }
+        for (name, value) in variables {
+            self.print(&format!("{} = {}", name, value.repr()));
+        }
+    }
+
+    /// Writes source line `line`, if there is one.
+    fn show_line(&mut self, line: usize, marker: &str) {
+        let source = &self.source;
+        if let Some(text) = line.checked_sub(1).and_then(|i| source.get(i)) {
+            let _ = writeln!(self.output, "{:>2} {:>4} | {}", marker, line, text);
+        }
+    }
+}
+
+impl<R: BufRead> DebugHook for Console<R> {
+    fn before_instruction(&mut self, vm: &VM) -> bool {
+        match self.debugger.check(vm.ip()) {
+            Some(stop) => self.stopped(vm, stop),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Console;
+    use crate::compiler::compiler::Compiler;
+    use crate::compiler::peephole::OptLevel;
+    use crate::error::vm::VMError;
+    use crate::output::sink::OutputBuffer;
+    use crate::vm::vm::VM;
+    use std::io::Cursor;
+
+    const SOURCE: &str = "var a = 1;
+{
+  var b = a + 1;
+  print b;
+}
+print a;";
+
+    /// Debugs `SOURCE` with the commands of `input`, returning everything written and
+    /// whether the script ran to its end.
+    fn debug(input: &str) -> (String, bool) {
+        let mut compiler = Compiler::new();
+        compiler.set_opt_level(OptLevel::None);
+        let chunk = compiler.compile(SOURCE).unwrap();
+
+        let output = OutputBuffer::new();
+        let mut console = Console::new(&chunk, Cursor::new(input.to_string()));
+        console.set_output(output.sink());
+        console.set_source(SOURCE);
+
+        let mut vm = VM::new();
+        vm.set_output(output.sink());
+        let finished = match vm.interpret_with(chunk, &mut console) {
+            Ok(_) => true,
+            Err(VMError::Interrupted) => {
+                assert!(console.has_quit());
+                false
+            }
+            Err(e) => panic!("{}", e),
+        };
+
+        (output.take(), finished)
+    }
+
+    #[test]
+    fn test_session() {
+        let (output, finished) = debug("b 4\nc\np b\np a\np c\nlocals\nglobals\nstack\nn\n\nl\n");
+
+        let expected = "Stopped at line 1 (entry)
+      1 | var a = 1;
+(debug) Breakpoint on line 4
+(debug) Stopped at line 4 (breakpoint)
+      4 |   print b;
+(debug) b = 2
+(debug) a = 1
+(debug) No variable named c
+(debug) b = 2
+(debug) a = 1
+(debug) [0] 2
+(debug) 2
+Stopped at line 5 (step)
+      5 | }
+(debug) Stopped at line 6 (step)
+      6 | print a;
+(debug)       4 |   print b;
+      5 | }
+->    6 | print a;
+(debug) \n";
+        assert_eq!(expected, output);
+        assert!(!finished);
+    }
+
+    #[test]
+    fn test_breakpoints_and_quit() {
+        let (output, finished) =
+            debug("b 2\nb 9\nbreakpoints\nd 3\nd 2\nbreakpoints\nbreak x\nquit\n");
+
+        assert!(output.contains("(debug) Breakpoint on line 3\n"));
+        assert!(output.contains("(debug) No code on or after line 9\n"));
+        assert!(output.contains("(debug) Breakpoints on lines 3\n"));
+        assert!(output.contains("(debug) Deleted the breakpoint on line 3\n"));
+        assert!(output.contains("(debug) No breakpoint on line 2\n"));
+        assert!(output.contains("(debug) No breakpoints\n"));
+        assert!(output.contains("(debug) Usage: break <line>\n"));
+        assert!(!finished);
+
+        let (output, finished) = debug("out\n");
+        assert!(output.ends_with("(debug) 2\n1\n"));
+        assert!(finished);
+    }
+}
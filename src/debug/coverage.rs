@@ -0,0 +1,255 @@
+use crate::bytecode::chunk::Chunk;
+use crate::bytecode::instruction::decode;
+use crate::bytecode::opcode::OpCode;
+use crate::vm::hook::DebugHook;
+use crate::vm::vm::VM;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Counts how many times each instruction of a chunk runs.
+pub struct Coverage {
+    // Source line of each code offset.
+    lines: Vec<usize>,
+    // Offsets where instructions start.
+    instructions: Vec<usize>,
+    // Runs of the instruction at each offset.
+    hits: Vec<u64>,
+}
+
+impl Coverage {
+    pub fn new(chunk: &Chunk) -> Self {
+        let mut instructions = Vec::new();
+        let mut offset = 0;
+        while offset < chunk.code.len() {
+            match decode(&chunk.code, offset) {
+                Ok(instruction) => {
+                    instructions.push(offset);
+                    offset = instruction.next_offset();
+                }
+                // The verifier rejects such chunks before they run.
+                Err(_) => break,
+            }
+        }
+        // The return ending the chunk has no source of its own but the end of the file.
+        if instructions.last().map(|offset| chunk.code[*offset]) == Some(OpCode::OP_RETURN as u8) {
+            instructions.pop();
+        }
+
+        Coverage {
+            lines: chunk.code_lines(),
+            instructions,
+            hits: vec![0; chunk.code.len()],
+        }
+    }
+
+    /// Offsets of the instructions that ran, with their runs.
+    pub fn offsets(&self) -> Vec<(usize, u64)> {
+        self.instructions
+            .iter()
+            .filter(|offset| self.hits[**offset] > 0)
+            .map(|offset| (*offset, self.hits[*offset]))
+            .collect()
+    }
+
+    /// Instructions that ran, out of all those of the chunk.
+    pub fn instructions(&self) -> (usize, usize) {
+        (self.offsets().len(), self.instructions.len())
+    }
+
+    /// Runs of each line with code, those of its most run instruction.
+    pub fn lines(&self) -> BTreeMap<usize, u64> {
+        let mut lines = BTreeMap::new();
+        for offset in self.instructions.iter() {
+            let runs = lines.entry(self.lines[*offset]).or_insert(0);
+            *runs = self.hits[*offset].max(*runs);
+        }
+
+        lines
+    }
+}
+
+impl DebugHook for Coverage {
+    fn before_instruction(&mut self, vm: &VM) -> bool {
+        if let Some(hits) = self.hits.get_mut(vm.ip()) {
+            *hits += 1;
+        }
+
+        true
+    }
+}
+
+/// Runs of the lines of source files, in the lcov tracefile format. Reports add up, so
+/// coverage is merged across runs by adding each to the report of the previous ones.
+#[derive(Debug, Default, PartialEq)]
+pub struct Lcov {
+    files: BTreeMap<String, BTreeMap<usize, u64>>,
+}
+
+impl Lcov {
+    pub fn new() -> Self {
+        Lcov::default()
+    }
+
+    /// Reads a tracefile, keeping the runs of lines and ignoring the other records.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lcov = Lcov::new();
+        let mut file = None;
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if let Some(path) = line.strip_prefix("SF:") {
+                file = Some(path.to_string());
+                lcov.files.entry(path.to_string()).or_default();
+            } else if let Some(data) = line.strip_prefix("DA:") {
+                let path = file
+                    .as_ref()
+                    .ok_or_else(|| format!("Line {} is outside of a file record", i + 1))?;
+                let mut fields = data.split(',').map(|field| field.parse::<u64>().ok());
+                let (number, runs) = match (fields.next(), fields.next()) {
+                    (Some(Some(number)), Some(Some(runs))) => (number as usize, runs),
+                    _ => return Err(format!("Invalid line data on line {}", i + 1)),
+                };
+                *lcov.files.get_mut(path).unwrap().entry(number).or_insert(0) += runs;
+            } else if line == "end_of_record" {
+                file = None;
+            }
+        }
+
+        Ok(lcov)
+    }
+
+    /// Adds the runs of `coverage` to those of `file`.
+    pub fn add(&mut self, file: &str, coverage: &Coverage) {
+        let lines = self.files.entry(file.to_string()).or_default();
+        for (line, runs) in coverage.lines() {
+            *lines.entry(line).or_insert(0) += runs;
+        }
+    }
+
+    /// Lines covered out of those with code, then the lines never run.
+    pub fn lines(&self, file: &str) -> (usize, usize, Vec<usize>) {
+        let lines = match self.files.get(file) {
+            Some(lines) => lines,
+            None => return (0, 0, Vec::new()),
+        };
+        let missed: Vec<usize> = lines
+            .iter()
+            .filter(|(_, runs)| **runs == 0)
+            .map(|(line, _)| *line)
+            .collect();
+
+        (lines.len() - missed.len(), lines.len(), missed)
+    }
+
+    /// A short account of the coverage of `file`.
+    pub fn summary(&self, file: &str) -> String {
+        let (covered, total, missed) = self.lines(file);
+        let percent = if total > 0 {
+            covered as f64 * 100.0 / total as f64
+        } else {
+            100.0
+        };
+
+        let mut summary = format!(
+            "{}: {} of {} lines covered ({:.1}%), 0 of 0 branches\n",
+            file, covered, total, percent
+        );
+        if !missed.is_empty() {
+            let missed: Vec<String> = missed.iter().map(|line| line.to_string()).collect();
+            summary.push_str(&format!("Lines never run: {}\n", missed.join(", ")));
+        }
+
+        summary
+    }
+}
+
+impl Display for Lcov {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (file, lines) in self.files.iter() {
+            writeln!(f, "TN:\nSF:{}\nFNF:0\nFNH:0", file)?;
+            for (line, runs) in lines.iter() {
+                writeln!(f, "DA:{},{}", line, runs)?;
+            }
+            let (covered, total, _) = self.lines(file);
+            writeln!(f, "LF:{}\nLH:{}", total, covered)?;
+            // Without conditional jumps there are no branches to record.
+            writeln!(f, "BRF:0\nBRH:0\nend_of_record")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Coverage, Lcov};
+    use crate::compiler::compiler::Compiler;
+    use crate::compiler::peephole::OptLevel;
+    use crate::output::sink::OutputBuffer;
+    use crate::vm::vm::VM;
+
+    const SOURCE: &str = "var a = 1;
+
+{
+  var b = a;
+  print b;
+}
+print -a;
+print -\"oops\";
+print a;";
+
+    fn cover(source: &str) -> Coverage {
+        let mut compiler = Compiler::new();
+        compiler.set_opt_level(OptLevel::None);
+        let chunk = compiler.compile(source).unwrap();
+
+        let mut coverage = Coverage::new(&chunk);
+        let mut vm = VM::new();
+        vm.set_output(OutputBuffer::new().sink());
+        let _ = vm.interpret_with(chunk, &mut coverage);
+
+        coverage
+    }
+
+    #[test]
+    fn test_coverage() {
+        let coverage = cover(SOURCE);
+
+        // The runtime error stops the script before the last print.
+        let lines: Vec<(usize, u64)> = coverage.lines().into_iter().collect();
+        assert_eq!(
+            vec![(1, 1), (4, 1), (5, 1), (6, 1), (7, 1), (8, 1), (9, 0)],
+            lines
+        );
+        assert_eq!((11, 14), coverage.instructions());
+        assert_eq!((0, 1), coverage.offsets()[0]);
+    }
+
+    #[test]
+    fn test_report() {
+        let coverage = cover(SOURCE);
+        let mut lcov = Lcov::new();
+        lcov.add("a.kt", &coverage);
+        lcov.add("a.kt", &coverage);
+
+        let report = lcov.to_string();
+        assert!(report.starts_with("TN:\nSF:a.kt\nFNF:0\nFNH:0\nDA:1,2\nDA:4,2\n"));
+        assert!(report.ends_with("DA:9,0\nLF:7\nLH:6\nBRF:0\nBRH:0\nend_of_record\n"));
+        assert_eq!(
+            "a.kt: 6 of 7 lines covered (85.7%), 0 of 0 branches\nLines never run: 9\n",
+            lcov.summary("a.kt")
+        );
+
+        let mut merged = Lcov::parse(&report).unwrap();
+        merged.add("a.kt", &cover(&SOURCE.replace("\"oops\"", "1")));
+        assert_eq!((7, 7, vec![]), merged.lines("a.kt"));
+        assert!(merged.to_string().contains("\nDA:1,3\n"));
+        assert_eq!(
+            Lcov::parse("SF:b.kt\nDA:2,1\nend_of_record\n").unwrap(),
+            Lcov::parse("TN:x\nSF:b.kt\nDA:2,0\nDA:2,1,abc\nLH:1\nend_of_record\n").unwrap()
+        );
+        assert!(Lcov::parse("DA:1,1\n").is_err());
+        assert!(Lcov::parse("SF:b.kt\nDA:x,1\n").is_err());
+    }
+}
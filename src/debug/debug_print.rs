@@ -1,9 +1,11 @@
 #[cfg(debug_assertions)]
+#[allow(unused_macros)]
 macro_rules! debug {
     ($( $args:expr ),*) => { println!( $( $args ),* ); }
 }
 
 #[cfg(not(debug_assertions))]
+#[allow(unused_macros)]
 macro_rules! debug {
     ($( $args:expr ),*) => {};
 }
@@ -0,0 +1,238 @@
+use crate::bytecode::chunk::Chunk;
+use crate::value::value::Value;
+use crate::vm::vm::VM;
+use std::collections::BTreeSet;
+use ustr::ustr;
+
+/// Why the execution stopped.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Stop {
+    Entry,
+    Breakpoint,
+    Step,
+}
+
+/// How the execution goes on after a stop.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Resume {
+    Continue,
+    StepIn,
+    StepOver,
+    StepOut,
+}
+
+/// Decides where a running chunk stops: at its first line, on breakpoints and after steps.
+/// Stops happen before the first instruction run on a line, each time execution enters it.
+pub struct Debugger {
+    // Source line of each code offset.
+    lines: Vec<usize>,
+    breakpoints: BTreeSet<usize>,
+    // How to go on from the last stop, `None` before the first one.
+    resume: Option<Resume>,
+    // Line of the last instruction checked.
+    line: Option<usize>,
+}
+
+impl Debugger {
+    /// A debugger for `chunk` that stops before its first instruction, unless resumed
+    /// before the chunk starts running.
+    pub fn new(chunk: &Chunk) -> Self {
+        Debugger {
+            lines: chunk.code_lines(),
+            breakpoints: BTreeSet::new(),
+            resume: None,
+            line: None,
+        }
+    }
+
+    /// Source line of the instruction at `offset`.
+    pub fn line_at(&self, offset: usize) -> usize {
+        self.lines.get(offset).copied().unwrap_or_default()
+    }
+
+    /// Sets a breakpoint on the first line from `line` on that has code, and returns that
+    /// line, or `None` if there is no code past `line`.
+    pub fn set_breakpoint(&mut self, line: usize) -> Option<usize> {
+        let line = self.lines.iter().copied().filter(|l| *l >= line).min()?;
+        self.breakpoints.insert(line);
+
+        Some(line)
+    }
+
+    /// Removes the breakpoint on `line`, returning whether there was one.
+    pub fn clear_breakpoint(&mut self, line: usize) -> bool {
+        self.breakpoints.remove(&line)
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = usize> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    pub fn resume(&mut self, resume: Resume) {
+        self.resume = Some(resume);
+    }
+
+    /// Whether to stop before running the instruction at `offset`, and why.
+    pub fn check(&mut self, offset: usize) -> Option<Stop> {
+        let line = self.line_at(offset);
+        if self.line == Some(line) {
+            return None;
+        }
+        self.line = Some(line);
+
+        match self.resume {
+            None => Some(Stop::Entry),
+            _ if self.breakpoints.contains(&line) => Some(Stop::Breakpoint),
+            Some(Resume::StepIn) | Some(Resume::StepOver) => Some(Stop::Step),
+            Some(Resume::Continue) | Some(Resume::StepOut) => None,
+        }
+    }
+}
+
+/// The locals in scope before the next instruction of `vm`, with their values, from the
+/// outermost to the innermost.
+pub fn locals(vm: &VM) -> Vec<(String, Value)> {
+    let chunk = match vm.chunk() {
+        Some(chunk) => chunk,
+        None => return Vec::new(),
+    };
+
+    chunk
+        .live_locals(vm.ip())
+        .filter_map(|local| {
+            let value = vm.stack().get(local.slot)?;
+            Some((local.name.clone(), value.clone()))
+        })
+        .collect()
+}
+
+/// Global variables sorted by name.
+pub fn globals(vm: &VM) -> Vec<(String, Value)> {
+    let mut globals: Vec<(String, Value)> = vm
+        .globals()
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.clone()))
+        .collect();
+    globals.sort_by(|a, b| a.0.cmp(&b.0));
+
+    globals
+}
+
+/// The value `name` refers to before the next instruction of `vm`: the innermost local of
+/// that name, or else the global.
+pub fn lookup(vm: &VM, name: &str) -> Option<Value> {
+    let local = locals(vm)
+        .into_iter()
+        .rev()
+        .find(|(local, _)| local == name);
+
+    match local {
+        Some((_, value)) => Some(value),
+        None => vm.globals().get(&ustr(name)).cloned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lookup, Debugger, Resume, Stop};
+    use crate::bytecode::chunk::Chunk;
+    use crate::compiler::compiler::Compiler;
+    use crate::compiler::peephole::OptLevel;
+    use crate::output::sink::OutputBuffer;
+    use crate::vm::hook::DebugHook;
+    use crate::vm::vm::VM;
+
+    const SOURCE: &str = "var a = 1;
+{
+  var b = a + 1;
+
+  print b;
+}
+print a;";
+
+    fn compile(source: &str) -> Chunk {
+        let mut compiler = Compiler::new();
+        compiler.set_opt_level(OptLevel::None);
+        compiler.compile(source).unwrap()
+    }
+
+    /// Stops as told by a debugger and records the line of every stop, along with the
+    /// value of `b` there.
+    struct Recorder {
+        debugger: Debugger,
+        resume: Resume,
+        stops: Vec<(Stop, usize, Option<String>)>,
+    }
+
+    impl DebugHook for Recorder {
+        fn before_instruction(&mut self, vm: &VM) -> bool {
+            if let Some(stop) = self.debugger.check(vm.ip()) {
+                let b = lookup(vm, "b").map(|b| b.to_string());
+                self.stops.push((stop, self.debugger.line_at(vm.ip()), b));
+                self.debugger.resume(self.resume);
+            }
+            true
+        }
+    }
+
+    fn stops(resume: Resume, breakpoints: &[usize]) -> Vec<(Stop, usize, Option<String>)> {
+        let chunk = compile(SOURCE);
+        let mut recorder = Recorder {
+            debugger: Debugger::new(&chunk),
+            resume,
+            stops: Vec::new(),
+        };
+        for line in breakpoints {
+            recorder.debugger.set_breakpoint(*line);
+        }
+
+        let mut vm = VM::new();
+        vm.set_output(OutputBuffer::new().sink());
+        vm.interpret_with(chunk, &mut recorder).unwrap();
+        recorder.stops
+    }
+
+    #[test]
+    fn test_steps() {
+        let b = Some(String::from("2"));
+        assert_eq!(
+            vec![
+                (Stop::Entry, 1, None),
+                (Stop::Step, 3, None),
+                (Stop::Step, 5, b),
+                // Popping the locals of the block, which are out of scope.
+                (Stop::Step, 6, None),
+                (Stop::Step, 7, None),
+            ],
+            stops(Resume::StepOver, &[])
+        );
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        assert_eq!(
+            vec![
+                (Stop::Entry, 1, None),
+                (Stop::Breakpoint, 5, Some(String::from("2"))),
+            ],
+            stops(Resume::Continue, &[4])
+        );
+        assert_eq!(vec![(Stop::Entry, 1, None)], stops(Resume::StepOut, &[]));
+    }
+
+    #[test]
+    fn test_set_breakpoint() {
+        let mut debugger = Debugger::new(&compile(SOURCE));
+
+        assert_eq!(Some(3), debugger.set_breakpoint(2));
+        assert_eq!(Some(7), debugger.set_breakpoint(7));
+        assert_eq!(None, debugger.set_breakpoint(8));
+        assert_eq!(vec![3, 7], debugger.breakpoints().collect::<Vec<_>>());
+        assert!(debugger.clear_breakpoint(3));
+        assert!(!debugger.clear_breakpoint(3));
+    }
+}
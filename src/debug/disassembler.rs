@@ -1,103 +1,232 @@
 use crate::bytecode::chunk::Chunk;
+use crate::bytecode::instruction::{decode, DecodeError};
 use crate::bytecode::opcode::OpCode;
-use crate::util::byte_utils::byte_array_to_u32;
-use std::convert::TryFrom;
-use std::borrow::Borrow;
+use crate::util::json::Json;
+use crate::value::value::Value;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// An instruction of a chunk resolved against the line table and the constant pool.
+#[derive(Debug, Clone)]
+pub struct DisassembledInstruction {
+    pub offset: usize,
+    pub line: usize,
+    pub op: OpCode,
+    pub operand: Option<usize>,
+    pub second_operand: Option<usize>,
+    /// The constant the operand points to, for opcodes with a constant operand.
+    pub constant: Option<Value>,
+}
 
-pub fn disassemble_chunk(chunk: &Chunk, name: &str) {
-    println!("== {} ==", name);
+impl DisassembledInstruction {
+    pub fn to_json(&self) -> Json {
+        let optional = |operand: Option<usize>| operand.map_or(Json::Null, Json::from);
+
+        Json::object(vec![
+            ("offset", Json::from(self.offset)),
+            ("line", Json::from(self.line)),
+            ("op", Json::from(format!("{:?}", self.op))),
+            ("operand", optional(self.operand)),
+            ("second_operand", optional(self.second_operand)),
+            (
+                "constant",
+                self.constant.as_ref().map_or(Json::Null, constant_to_json),
+            ),
+        ])
+    }
 
-    let mut offset: usize = 0;
+    fn write(&self, f: &mut Formatter<'_>, line: &str) -> fmt::Result {
+        write!(f, "{:04} {:>4} ", self.offset, line)?;
+
+        let op = format!("{:?}", self.op);
+        if let Some(second) = self.second_operand {
+            write!(
+                f,
+                "{:<18} {:>4} {:>4}",
+                op,
+                self.operand.unwrap_or(0),
+                second
+            )?;
+            return match &self.constant {
+                Some(constant) => write!(f, " '{}'", constant),
+                None => Ok(()),
+            };
+        }
 
-    while offset < chunk.code.len() {
-        offset = disassemble_instruction(chunk, offset);
+        match (self.operand, &self.constant) {
+            (Some(operand), Some(constant)) => {
+                write!(f, "{:<18} {:>4} '{}'", op, operand, constant)
+            }
+            (Some(operand), None) => write!(f, "{:<18} {:>4}", op, operand),
+            _ => write!(f, "{}", op),
+        }
     }
 }
 
-pub fn disassemble_instruction(chunk: &Chunk, offset: usize) -> usize {
-    if offset > 0 && chunk.get_code_line(offset) == chunk.get_code_line(offset - 1) {
-        print!("|     {offset:>04} ", offset = offset);
-    } else {
-        print!(
-            "{line}:    {offset:>04} ",
-            line = chunk.get_code_line(offset),
-            offset = offset
-        );
+impl Display for DisassembledInstruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.write(f, &self.line.to_string())
     }
+}
 
-    let op_code = chunk.code.get(offset).unwrap();
-    let op_enum = OpCode::try_from(*op_code).expect("Unable to serialize byte to OpCode");
-
-    match op_enum {
-        OpCode::OP_RETURN
-        | OpCode::OP_NEGATE
-        | OpCode::OP_ADD
-        | OpCode::OP_SUB
-        | OpCode::OP_MUL
-        | OpCode::OP_DIV
-        | OpCode::OP_NIL
-        | OpCode::OP_TRUE
-        | OpCode::OP_FALSE
-        | OpCode::OP_NOT
-        | OpCode::OP_LESS
-        | OpCode::OP_GREATER
-        | OpCode::OP_EQUAL
-        | OpCode::OP_PRINT
-        | OpCode::OP_POP => simple(op_enum, *op_code, offset),
-        OpCode::OP_CONST
-        | OpCode::OP_GET_GLOBAL
-        | OpCode::OP_DEF_GLOBAL
-        | OpCode::OP_SET_GLOBAL => constant(op_enum, *op_code, chunk, offset),
-        OpCode::OP_SET_LOCAL | OpCode::OP_GET_LOCAL => byte_instr(op_enum, *op_code, chunk, offset),
-        _ => {
-            print!("UNKNOWN {}", *op_code);
-            offset
-        }
-    }
+/// All instructions of a chunk, shown as a listing by `Display` or as JSON by `to_json`.
+pub struct Disassembly {
+    pub name: String,
+    pub instructions: Vec<DisassembledInstruction>,
 }
 
-fn simple(op: OpCode, op_num: u8, offset: usize) -> usize {
-    print!("{:?} {}", op, op_num);
-    offset + 1
+impl Disassembly {
+    pub fn new(chunk: &Chunk, name: &str) -> Result<Self, DecodeError> {
+        Ok(Disassembly {
+            name: String::from(name),
+            instructions: decode_chunk(chunk)?,
+        })
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("name", Json::from(self.name.as_str())),
+            (
+                "instructions",
+                Json::Array(self.instructions.iter().map(|i| i.to_json()).collect()),
+            ),
+        ])
+    }
 }
 
-fn constant(op: OpCode, op_num: u8, chunk: &Chunk, offset: usize) -> usize {
-    let constant_index = chunk.code.get(offset + 1).unwrap();
-    let constant_value = chunk
-        .const_pool
-        .values
-        .get(*constant_index as usize)
-        .unwrap();
+impl Display for Disassembly {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "== {} ==", self.name)?;
 
-    print!(
-        "{:?} {} {:>4} {}",
-        op, op_num, constant_index, constant_value
-    );
+        let mut previous_line = None;
+        for instruction in self.instructions.iter() {
+            if previous_line == Some(instruction.line) {
+                instruction.write(f, "|")?;
+            } else {
+                instruction.write(f, &instruction.line.to_string())?;
+            }
+            writeln!(f)?;
 
-    offset + 2
+            previous_line = Some(instruction.line);
+        }
+
+        Ok(())
+    }
 }
 
-fn byte_instr(op: OpCode, op_num: u8, chunk: &Chunk, offset: usize) -> usize {
-    let slot = chunk.code.get(offset + 1).unwrap();
-    print!("{:?} {} {}", op.borrow(), op_num, slot);
+pub fn decode_instruction(
+    chunk: &Chunk,
+    offset: usize,
+) -> Result<DisassembledInstruction, DecodeError> {
+    let decoded = decode(&chunk.code, offset)?;
+    let constant = match decoded.operand {
+        Some(index) if decoded.op.has_const_operand() => {
+            chunk.const_pool.values.get(index).cloned()
+        }
+        _ => None,
+    };
+
+    Ok(DisassembledInstruction {
+        offset,
+        line: chunk.get_code_line(offset),
+        op: decoded.op,
+        operand: decoded.operand,
+        second_operand: decoded.second_operand,
+        constant,
+    })
+}
 
-    offset + 2
+pub fn decode_chunk(chunk: &Chunk) -> Result<Vec<DisassembledInstruction>, DecodeError> {
+    let mut instructions = Vec::new();
+    let mut offset = 0;
+
+    while offset < chunk.code.len() {
+        let instruction = decode_instruction(chunk, offset)?;
+        offset += 1 + instruction.op.operand_len();
+        instructions.push(instruction);
+    }
+
+    Ok(instructions)
 }
 
-fn constant_long(op: u8, chunk: &Chunk, offset: usize) -> usize {
-    let bytes = chunk.get_byte_sequence(offset + 1, offset + 3);
-    let constant_index = byte_array_to_u32(&[0, bytes[0], bytes[1], bytes[2]]);
+fn constant_to_json(value: &Value) -> Json {
+    match value {
+        Value::Number(n) if n.is_finite() => Json::from(*n),
+        Value::Bool(b) => Json::from(*b),
+        Value::Nil => Json::Null,
+        _ => Json::from(value.to_string()),
+    }
+}
 
-    let constant_value = chunk
-        .const_pool
-        .values
-        .get(constant_index as usize)
-        .unwrap();
+#[cfg(test)]
+mod tests {
+    use super::{decode_chunk, Disassembly};
+    use crate::bytecode::chunk::Chunk;
+    use crate::bytecode::opcode::OpCode;
+    use crate::value::value::Value;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_every_opcode() {
+        let ops: Vec<OpCode> = (0..=u8::MAX)
+            .filter_map(|byte| OpCode::try_from(byte).ok())
+            .collect();
+
+        let mut chunk = Chunk::new();
+        chunk.add_const(Value::from("name"));
+        for (line, op) in ops.iter().enumerate() {
+            chunk.write_code(*op, line);
+            for _ in 0..op.operand_len() {
+                chunk.write_byte(0, line);
+            }
+        }
 
-    print!(
-        "OP_CONST_LONG {} {:>4} {}",
-        op, constant_index, constant_value
-    );
+        let instructions = decode_chunk(&chunk).unwrap();
+        assert_eq!(ops, instructions.iter().map(|i| i.op).collect::<Vec<_>>());
+        for (line, instruction) in instructions.iter().enumerate() {
+            assert_eq!(line, instruction.line);
+            assert_eq!(
+                instruction.op.has_const_operand(),
+                instruction.constant.is_some()
+            );
+            assert!(instruction
+                .to_string()
+                .contains(&format!("{:?}", instruction.op)));
+        }
+    }
 
-    offset + 4
+    #[test]
+    fn test_renderers() {
+        let mut chunk = Chunk::new();
+        chunk.add_const(Value::Number(1.5));
+        chunk.add_const(Value::from("a\"b"));
+        chunk.write_code(OpCode::OP_CONST, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_code(OpCode::OP_CONST_LONG, 1);
+        chunk.write_byte(1, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_code(OpCode::OP_ADD_CONST_LOCAL, 2);
+        chunk.write_byte(0, 2);
+        chunk.write_byte(3, 2);
+        chunk.write_code(OpCode::OP_RETURN, 2);
+
+        let disassembly = Disassembly::new(&chunk, "test").unwrap();
+        assert_eq!(
+            "== test ==\n\
+             0000    1 OP_CONST              0 '1.5'\n\
+             0002    | OP_CONST_LONG         1 'a\"b'\n\
+             0006    2 OP_ADD_CONST_LOCAL    0    3 '1.5'\n\
+             0009    | OP_RETURN\n",
+            disassembly.to_string()
+        );
+        assert_eq!(
+            "{\"name\":\"test\",\"instructions\":[\
+             {\"offset\":0,\"line\":1,\"op\":\"OP_CONST\",\"operand\":0,\"second_operand\":null,\"constant\":1.5},\
+             {\"offset\":2,\"line\":1,\"op\":\"OP_CONST_LONG\",\"operand\":1,\"second_operand\":null,\"constant\":\"a\\\"b\"},\
+             {\"offset\":6,\"line\":2,\"op\":\"OP_ADD_CONST_LOCAL\",\"operand\":0,\"second_operand\":3,\"constant\":1.5},\
+             {\"offset\":9,\"line\":2,\"op\":\"OP_RETURN\",\"operand\":null,\"second_operand\":null,\"constant\":null}]}",
+            disassembly.to_json().to_string()
+        );
+    }
 }
@@ -1,4 +1,8 @@
+pub mod console;
+pub mod coverage;
+pub mod debugger;
 pub mod disassembler;
+pub mod profiler;
 
 #[macro_use]
 pub mod debug_print;
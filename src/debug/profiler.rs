@@ -0,0 +1,245 @@
+use crate::bytecode::chunk::Chunk;
+use crate::bytecode::opcode::OpCode;
+use crate::vm::hook::DebugHook;
+use crate::vm::vm::VM;
+use std::cmp::Reverse;
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+// Name of the frame the whole script runs in. Without functions it is the only frame, and
+// its inclusive and exclusive times are both the total.
+const SCRIPT: &str = "<script>";
+
+/// Counts the instructions a chunk runs, by opcode and by source line, and times each
+/// line. An instruction takes the time from its hook call to the next one, so the timings
+/// include the overhead of the profiler itself.
+pub struct Profiler {
+    // Source line of each code offset.
+    lines: Vec<usize>,
+    // Instructions run, indexed by opcode byte.
+    opcodes: Vec<u64>,
+    // Instructions run and time spent, indexed by line.
+    line_instructions: Vec<u64>,
+    line_times: Vec<Duration>,
+    // Line of the running instruction and when it started.
+    running: Option<(usize, Instant)>,
+}
+
+impl Profiler {
+    pub fn new(chunk: &Chunk) -> Self {
+        let line_count = chunk.line_code_index().len();
+
+        Profiler {
+            lines: chunk.code_lines(),
+            opcodes: vec![0; usize::from(u8::MAX) + 1],
+            line_instructions: vec![0; line_count],
+            line_times: vec![Duration::default(); line_count],
+            running: None,
+        }
+    }
+
+    /// Stops the clock of the last instruction, once the chunk is done running.
+    pub fn finish(&mut self) {
+        self.charge(Instant::now());
+        self.running = None;
+    }
+
+    pub fn instructions(&self) -> u64 {
+        self.opcodes.iter().sum()
+    }
+
+    pub fn time(&self) -> Duration {
+        self.line_times.iter().sum()
+    }
+
+    /// Instructions run and time spent on each line that ran.
+    pub fn line_profile(&self) -> Vec<(usize, u64, Duration)> {
+        self.line_instructions
+            .iter()
+            .zip(self.line_times.iter())
+            .enumerate()
+            .filter(|(_, (count, _))| **count > 0)
+            .map(|(line, (count, time))| (line, *count, *time))
+            .collect()
+    }
+
+    /// Instructions run for each opcode that ran, the most frequent first.
+    pub fn opcode_counts(&self) -> Vec<(OpCode, u64)> {
+        let mut counts: Vec<(OpCode, u64)> = self
+            .opcodes
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .filter_map(|(byte, count)| Some((OpCode::try_from(byte as u8).ok()?, *count)))
+            .collect();
+        counts.sort_by_key(|(_, count)| Reverse(*count));
+
+        counts
+    }
+
+    /// The profile as text: the functions, then the lines and the opcodes.
+    pub fn report(&self) -> String {
+        let total = self.time();
+        let instructions = self.instructions();
+        let mut report = String::new();
+
+        let _ = writeln!(
+            report,
+            "{} instructions in {}\n",
+            instructions,
+            millis(total)
+        );
+
+        let _ = writeln!(
+            report,
+            "{:<12} {:>20} {:>20}",
+            "Function", "Inclusive", "Exclusive"
+        );
+        let time = format!("{} {}", millis(total), share(total, total));
+        let _ = writeln!(report, "{:<12} {:>20} {:>20}\n", SCRIPT, time, time);
+
+        let _ = writeln!(
+            report,
+            "{:>6} {:>14} {:>20}",
+            "Line", "Instructions", "Time"
+        );
+        for (line, count, time) in self.line_profile() {
+            let time = format!("{} {}", millis(time), share(time, total));
+            let _ = writeln!(report, "{:>6} {:>14} {:>20}", line, count, time);
+        }
+
+        let _ = writeln!(report, "\n{:<20} {:>14}", "Opcode", "Instructions");
+        for (opcode, count) in self.opcode_counts() {
+            let count = format!(
+                "{} {:>5.1}%",
+                count,
+                percent(count as f64, instructions as f64)
+            );
+            let _ = writeln!(report, "{:<20} {:>14}", format!("{:?}", opcode), count);
+        }
+
+        report
+    }
+
+    /// The time of each line as collapsed stacks, one `<script>;<name>:<line> <ns>` line
+    /// each, as flame graph tools read them.
+    pub fn collapsed(&self, name: &str) -> String {
+        let mut collapsed = String::new();
+        for (line, _, time) in self.line_profile() {
+            let _ = writeln!(
+                collapsed,
+                "{};{}:{} {}",
+                SCRIPT,
+                name,
+                line,
+                time.as_nanos()
+            );
+        }
+
+        collapsed
+    }
+
+    fn charge(&mut self, now: Instant) {
+        if let Some((line, start)) = self.running {
+            self.line_times[line] += now.duration_since(start);
+        }
+    }
+}
+
+impl DebugHook for Profiler {
+    fn before_instruction(&mut self, vm: &VM) -> bool {
+        let now = Instant::now();
+        self.charge(now);
+
+        let offset = vm.ip();
+        if let Some(byte) = vm.chunk().and_then(|chunk| chunk.code.get(offset)) {
+            self.opcodes[usize::from(*byte)] += 1;
+        }
+        let line = self.lines.get(offset).copied().unwrap_or_default();
+        self.line_instructions[line] += 1;
+        self.running = Some((line, now));
+
+        true
+    }
+}
+
+fn millis(time: Duration) -> String {
+    format!("{:.3} ms", time.as_secs_f64() * 1000.0)
+}
+
+fn share(part: Duration, total: Duration) -> String {
+    format!("{:>5.1}%", percent(part.as_secs_f64(), total.as_secs_f64()))
+}
+
+fn percent(part: f64, total: f64) -> f64 {
+    if total > 0.0 {
+        part * 100.0 / total
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Profiler;
+    use crate::bytecode::opcode::OpCode;
+    use crate::compiler::compiler::Compiler;
+    use crate::compiler::peephole::OptLevel;
+    use crate::output::sink::OutputBuffer;
+    use crate::vm::vm::VM;
+
+    fn profile(source: &str) -> Profiler {
+        let mut compiler = Compiler::new();
+        compiler.set_opt_level(OptLevel::None);
+        let chunk = compiler.compile(source).unwrap();
+
+        let mut profiler = Profiler::new(&chunk);
+        let mut vm = VM::new();
+        vm.set_output(OutputBuffer::new().sink());
+        vm.interpret_with(chunk, &mut profiler).unwrap();
+        profiler.finish();
+
+        profiler
+    }
+
+    #[test]
+    fn test_counts() {
+        let profiler = profile("var a = 1;\n\nprint a + a;\nprint a;");
+
+        let lines: Vec<(usize, u64)> = profiler
+            .line_profile()
+            .into_iter()
+            .map(|(line, count, _)| (line, count))
+            .collect();
+        // The return of the chunk is on the last line.
+        assert_eq!(vec![(1, 2), (3, 4), (4, 3)], lines);
+        assert_eq!(9, profiler.instructions());
+
+        let opcodes = profiler.opcode_counts();
+        assert_eq!((OpCode::OP_GET_GLOBAL, 3), opcodes[0]);
+        assert_eq!(6, opcodes.len());
+        assert_eq!(
+            profiler.time(),
+            profiler.line_profile().iter().map(|l| l.2).sum()
+        );
+    }
+
+    #[test]
+    fn test_output() {
+        let profiler = profile("var a = 1;\nprint a;");
+
+        let report = profiler.report();
+        assert!(report.starts_with("5 instructions in "));
+        assert!(report.contains("\n<script>  "));
+        assert!(report.contains("\n     2              3 "));
+        assert!(report.contains("\nOP_GET_GLOBAL        "));
+
+        let collapsed = profiler.collapsed("a.kt");
+        let stacks: Vec<&str> = collapsed
+            .lines()
+            .map(|line| line.rsplit_once(' ').unwrap().0)
+            .collect();
+        assert_eq!(vec!["<script>;a.kt:1", "<script>;a.kt:2"], stacks);
+    }
+}
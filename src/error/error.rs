@@ -1,7 +1,8 @@
-use crate::scanner::token::Token;
+use crate::scanner::token::{Token, TokenType};
 use std::fmt;
 use std::fmt::{Display, Formatter};
 
+#[derive(Debug)]
 pub struct Error {
     token: Option<Token>,
     message: String,
@@ -21,12 +22,36 @@ impl Error {
             message: String::from(message),
         }
     }
+
+    /// The token the error was reported at, if any.
+    pub fn token(&self) -> Option<&Token> {
+        self.token.as_ref()
+    }
+
+    /// What went wrong, without the location.
+    pub fn description(&self) -> &str {
+        match &self.token {
+            Some(token) if token.token_type == TokenType::ERROR => &token.lexem,
+            _ => &self.message,
+        }
+    }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
         match &self.token {
-            Some(token) => write!(f, "{} {}", token, self.message),
+            // Scanner errors carry their message in the token.
+            Some(token) if token.token_type == TokenType::ERROR => {
+                write!(f, "[line {}] Error: {}", token.line, token.lexem)
+            }
+            Some(token) if token.token_type == TokenType::EOF => {
+                write!(f, "[line {}] Error at end: {}", token.line, self.message)
+            }
+            Some(token) => write!(
+                f,
+                "[line {}] Error at '{}': {}",
+                token.line, token.lexem, self.message
+            ),
             None => write!(f, "{}", self.message),
         }
     }
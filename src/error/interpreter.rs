@@ -1,11 +1,44 @@
 use crate::error::error::Error;
+use crate::error::vm::VMError;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 
+#[derive(Debug)]
 pub enum InterpreterError {
     CompilerError(Error),
     SyntaxError(Error),
     RuntimeError(Error),
+    BytecodeError(Error),
+    IoError(Error),
+    OutOfFuel,
+    Interrupted,
+}
+
+impl InterpreterError {
+    /// Process exit code following the BSD sysexits convention: `EX_DATAERR` for
+    /// input that does not compile or load, `EX_SOFTWARE` for runtime failures and
+    /// `EX_IOERR` when a file cannot be read or written.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            InterpreterError::CompilerError(_)
+            | InterpreterError::SyntaxError(_)
+            | InterpreterError::BytecodeError(_) => 65,
+            InterpreterError::RuntimeError(_)
+            | InterpreterError::OutOfFuel
+            | InterpreterError::Interrupted => 70,
+            InterpreterError::IoError(_) => 74,
+        }
+    }
+}
+
+impl From<VMError> for InterpreterError {
+    fn from(e: VMError) -> Self {
+        match e {
+            VMError::RuntimeError(err) => InterpreterError::RuntimeError(err),
+            VMError::OutOfFuel => InterpreterError::OutOfFuel,
+            VMError::Interrupted => InterpreterError::Interrupted,
+        }
+    }
 }
 
 impl Display for InterpreterError {
@@ -14,6 +47,10 @@ impl Display for InterpreterError {
             InterpreterError::CompilerError(err) => write!(f, "CompilerError: {}", err),
             InterpreterError::SyntaxError(err) => write!(f, "SyntaxError: {}", err),
             InterpreterError::RuntimeError(err) => write!(f, "RuntimeError: {}", err),
+            InterpreterError::BytecodeError(err) => write!(f, "BytecodeError: {}", err),
+            InterpreterError::IoError(err) => write!(f, "IoError: {}", err),
+            InterpreterError::OutOfFuel => write!(f, "RuntimeError: Out of fuel"),
+            InterpreterError::Interrupted => write!(f, "RuntimeError: Interrupted"),
         }
     }
 }
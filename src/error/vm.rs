@@ -0,0 +1,28 @@
+use crate::error::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub enum VMError {
+    RuntimeError(Error),
+    /// The instruction budget ran out. The execution is suspended and can be resumed.
+    OutOfFuel,
+    /// An `InterruptHandle` stopped the execution. It is suspended and can be resumed.
+    Interrupted,
+}
+
+impl VMError {
+    pub fn is_suspension(&self) -> bool {
+        matches!(self, VMError::OutOfFuel | VMError::Interrupted)
+    }
+}
+
+impl Display for VMError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            VMError::RuntimeError(err) => write!(f, "{}", err),
+            VMError::OutOfFuel => write!(f, "Out of fuel"),
+            VMError::Interrupted => write!(f, "Interrupted"),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::fmt::Write;
+
+const NAMES: [&str; 4] = ["a", "b", "c", "_x"];
+const BINARY: [&str; 10] = ["+", "-", "*", "/", "==", "!=", "<", "<=", ">", ">="];
+
+// Deeper expressions and blocks only repeat what shallow ones already cover.
+const MAX_DEPTH: usize = 4;
+
+/// Random inputs for the fuzz targets, from a xorshift generator so a seed always
+/// reproduces the same sequence.
+pub struct Generator {
+    state: u64,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Generator { state: seed.max(1) }
+    }
+
+    /// A program that follows the grammar, mostly valid but with no guarantee to compile
+    /// or to run without errors.
+    pub fn program(&mut self) -> String {
+        let mut program = String::new();
+        for _ in 0..self.below(8) + 1 {
+            self.statement(&mut program, 0);
+        }
+        program
+    }
+
+    /// `seed` with a few random bytes flipped, inserted, removed or repeated.
+    pub fn mutate(&mut self, seed: &[u8]) -> Vec<u8> {
+        let mut data = seed.to_vec();
+
+        for _ in 0..self.below(4) + 1 {
+            let at = self.below(data.len() + 1);
+            match self.below(4) {
+                0 if at < data.len() => data[at] ^= 1 << self.below(8),
+                1 => data.insert(at, self.next() as u8),
+                2 if at < data.len() => {
+                    data.remove(at);
+                }
+                _ => {
+                    let end = (at + self.below(16)).min(data.len());
+                    let copy = data[at..end].to_vec();
+                    let to = self.below(data.len() + 1);
+                    data.splice(to..to, copy);
+                }
+            }
+        }
+
+        data
+    }
+
+    /// Up to `max` arbitrary bytes.
+    pub fn bytes(&mut self, max: usize) -> Vec<u8> {
+        (0..self.below(max + 1))
+            .map(|_| self.next() as u8)
+            .collect()
+    }
+
+    fn statement(&mut self, out: &mut String, depth: usize) {
+        let line = [" ", " ", "\n", "\n\n", " // note\n"][self.below(5)];
+
+        match self.below(if depth < MAX_DEPTH { 6 } else { 5 }) {
+            0 => write!(out, "var {} = ", self.name()).unwrap(),
+            1 => out.push_str("print "),
+            2 => write!(out, "{} = ", self.name()).unwrap(),
+            3 => out.push_str("var _x;"),
+            4 => {}
+            _ => {
+                out.push('{');
+                for _ in 0..self.below(4) {
+                    self.statement(out, depth + 1);
+                }
+                out.push('}');
+                out.push_str(line);
+                return;
+            }
+        }
+
+        if !out.ends_with(';') {
+            self.expression(out, depth);
+            out.push(';');
+        }
+        out.push_str(line);
+    }
+
+    fn expression(&mut self, out: &mut String, depth: usize) {
+        match self.below(if depth < MAX_DEPTH { 10 } else { 6 }) {
+            0 => write!(out, "{}", self.below(1000)).unwrap(),
+            1 => write!(out, "{}.{}", self.below(100), self.below(100)).unwrap(),
+            2 => {
+                let string = ["", "a", "ab", "\u{e9}"][self.below(4)];
+                write!(out, "\"{}\"", string).unwrap()
+            }
+            3 => out.push_str(["true", "false", "nil"][self.below(3)]),
+            4 | 5 => out.push_str(self.name()),
+            6 => {
+                out.push_str(["-", "!"][self.below(2)]);
+                self.expression(out, depth + 1);
+            }
+            7 => {
+                out.push('(');
+                self.expression(out, depth + 1);
+                out.push(')');
+            }
+            _ => {
+                self.expression(out, depth + 1);
+                write!(out, " {} ", BINARY[self.below(BINARY.len())]).unwrap();
+                if self.below(8) == 0 {
+                    out.push_str("// operand\n");
+                }
+                self.expression(out, depth + 1);
+            }
+        }
+    }
+
+    fn name(&mut self) -> &'static str {
+        NAMES[self.below(NAMES.len())]
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+}
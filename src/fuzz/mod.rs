@@ -0,0 +1,200 @@
+//! Entry points for fuzzing the scanner, the compiler and the VM. Each target takes
+//! arbitrary bytes and panics only when an invariant is broken; every bad input must end
+//! in an `InterpreterError` instead. They are driven by `cargo fuzz` from the `fuzz`
+//! directory and by the generator in `tests/fuzz.rs`.
+
+pub mod generator;
+
+use crate::bytecode::binary::{deserialize, is_bytecode, serialize};
+use crate::bytecode::chunk::Chunk;
+use crate::compiler::compiler::Compiler;
+use crate::compiler::lint::LintConfig;
+use crate::compiler::peephole::OptLevel;
+use crate::error::vm::VMError;
+use crate::output::sink::OutputBuffer;
+use crate::scanner::scanner::Scanner;
+use crate::scanner::token::TokenType;
+use crate::syntax::formatter;
+use crate::value::value::{Value, ValuePool};
+use crate::vm::config::VMConfig;
+use crate::vm::vm::{VMRunResult, VM};
+
+// Keeps a single input from running, or allocating, for long.
+const FUEL: u64 = 100_000;
+const MAX_STACK: usize = 1024;
+const MAX_HEAP: usize = 1024 * 1024;
+
+/// Scans `data` as source until the end of input.
+pub fn scan(data: &[u8]) {
+    let source = String::from_utf8_lossy(data);
+    let mut scanner = Scanner::new(&source);
+
+    // Every token but the last consumes at least one character.
+    let limit = source.chars().count() + 1;
+    for _ in 0..=limit {
+        if scanner.scan_token().token_type == TokenType::EOF {
+            return;
+        }
+    }
+
+    panic!("scanner did not reach the end of {} characters", limit - 1);
+}
+
+/// Compiles and lints `data` as source. Whatever compiles must pass the verifier and
+/// survive a trip through the bytecode format.
+pub fn compile(data: &[u8]) -> Option<Chunk> {
+    let source = String::from_utf8_lossy(data);
+    let mut compiler = Compiler::new();
+    compiler.set_diagnostics(OutputBuffer::new().sink());
+    compiler.set_lints(Some(LintConfig::new()));
+    compiler.set_warnings(OutputBuffer::new().sink());
+
+    let chunk = compiler.compile(&source).ok()?;
+    if let Err(e) = chunk.verify() {
+        panic!("compiled chunk does not verify: {}", e);
+    }
+
+    let bytes = serialize(&chunk).expect("compiled chunk does not serialize");
+    let loaded = deserialize(&bytes).expect("serialized chunk does not load");
+    assert_eq!(chunk.code, loaded.code);
+    assert_eq!(chunk.line_code_index(), loaded.line_code_index());
+    assert_eq!(chunk.locals, loaded.locals);
+
+    Some(chunk)
+}
+
+/// Executes `data` as bytecode. Input in the `.kbc` format is loaded as such, anything
+/// else becomes the code of a chunk with a fixed constant pool.
+pub fn execute(data: &[u8]) {
+    let chunk = if is_bytecode(data) {
+        match deserialize(data) {
+            Ok(chunk) => chunk,
+            Err(_) => return,
+        }
+    } else {
+        raw_chunk(data)
+    };
+
+    let _ = run(chunk);
+}
+
+/// Compiles `data` with and without optimisations, both versions must print the same and
+/// fail with the same error.
+pub fn optimize(data: &[u8]) {
+    let source = String::from_utf8_lossy(data);
+    let mut results = Vec::new();
+
+    for level in [OptLevel::None, OptLevel::default()].iter() {
+        let mut compiler = Compiler::new();
+        compiler.set_diagnostics(OutputBuffer::new().sink());
+        compiler.set_opt_level(*level);
+
+        let chunk = match compiler.compile(&source).ok() {
+            Some(chunk) => chunk,
+            None => return,
+        };
+        match run(chunk) {
+            // Optimised code runs fewer instructions, so it may finish where the other did not.
+            (_, Err(VMError::OutOfFuel)) => return,
+            (output, result) => results.push((output, result.err().map(|e| e.to_string()))),
+        }
+    }
+
+    assert_eq!(results[0], results[1], "optimised code behaves differently");
+}
+
+/// Compiles `data` in a single pass and through a syntax tree, in and out of echo mode.
+/// Both must accept the same inputs and produce the same chunk.
+pub fn parse(data: &[u8]) {
+    let source = String::from_utf8_lossy(data);
+
+    for echo in [false, true].iter() {
+        let single_pass = compile_unoptimized(&source, *echo, true);
+        let tree = compile_unoptimized(&source, *echo, false);
+
+        match (single_pass, tree) {
+            (Some(single_pass), Some(tree)) => {
+                assert_eq!(
+                    single_pass.line_code_index(),
+                    tree.line_code_index(),
+                    "lines differ"
+                );
+                assert_same_code(&single_pass, &tree);
+            }
+            (None, None) => (),
+            (Some(_), None) => panic!("only the tree compilation failed"),
+            (None, Some(_)) => panic!("only the single-pass compilation failed"),
+        }
+    }
+}
+
+/// Formats `data` as source. Formatting again must change nothing, and the formatted
+/// source must compile to the same code, if on other lines.
+pub fn format(data: &[u8]) {
+    let source = String::from_utf8_lossy(data);
+    let formatted = match formatter::format(&source) {
+        Ok(formatted) => formatted,
+        Err(_) => return,
+    };
+
+    let again = formatter::format(&formatted).expect("formatted source does not parse");
+    assert_eq!(formatted, again, "formatting is not idempotent");
+
+    match (
+        compile_unoptimized(&source, false, false),
+        compile_unoptimized(&formatted, false, false),
+    ) {
+        (Some(chunk), Some(formatted)) => assert_same_code(&chunk, &formatted),
+        (None, None) => (),
+        _ => panic!("formatting changed whether the source compiles"),
+    }
+}
+
+fn compile_unoptimized(source: &str, echo: bool, single_pass: bool) -> Option<Chunk> {
+    let mut compiler = Compiler::new();
+    compiler.set_diagnostics(OutputBuffer::new().sink());
+    compiler.set_opt_level(OptLevel::None);
+    compiler.set_echo(echo);
+    compiler.set_single_pass(single_pass);
+
+    compiler.compile(source).ok()
+}
+
+fn assert_same_code(a: &Chunk, b: &Chunk) {
+    assert_eq!(a.code, b.code, "code differs");
+    assert_eq!(
+        format!("{:?}", a.const_pool.values),
+        format!("{:?}", b.const_pool.values),
+        "constants differ"
+    );
+    assert_eq!(a.locals, b.locals, "locals differ");
+}
+
+/// Runs `chunk` under the fuzzing limits, returning what it printed.
+pub fn run(chunk: Chunk) -> (String, VMRunResult<Chunk>) {
+    let mut vm = VM::with_config(VMConfig {
+        max_stack: MAX_STACK,
+        max_heap: MAX_HEAP,
+    });
+    let output = OutputBuffer::new();
+    vm.set_output(output.sink());
+    vm.set_fuel(Some(FUEL));
+
+    let result = vm.interpret(chunk);
+    (output.take(), result)
+}
+
+fn raw_chunk(data: &[u8]) -> Chunk {
+    let mut pool = ValuePool::new();
+    pool.values = vec![
+        Value::from("a"),
+        Value::from("b"),
+        Value::Number(1.0),
+        Value::Number(-0.5),
+        Value::from(""),
+        Value::Bool(true),
+        Value::Nil,
+    ];
+
+    Chunk::from_parts(data.to_vec(), pool, vec![data.len()])
+}
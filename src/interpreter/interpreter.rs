@@ -1,9 +1,22 @@
+use crate::bytecode::binary;
 use crate::bytecode::chunk::Chunk;
-use crate::compiler::compiler::Compiler;
+use crate::compiler::compilation::Compilation;
+use crate::compiler::lint::LintConfig;
+use crate::compiler::peephole::OptLevel;
+use crate::compiler::session::Session;
+use crate::debug::debugger;
+use crate::debug::disassembler::Disassembly;
 use crate::error::error::Error;
 use crate::error::interpreter::InterpreterError;
+use crate::interpreter::repl::Repl;
+use crate::output::sink::Sink;
+use crate::syntax::formatter;
+use crate::value::value::Value;
+use crate::vm::config::VMConfig;
+use crate::vm::hook::DebugHook;
+use crate::vm::interrupt::InterruptHandle;
 use crate::vm::vm::VM;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::Path;
 use std::{fs, io};
 
@@ -11,53 +24,284 @@ pub type InterpreterResult<T> = Result<T, InterpreterError>;
 
 pub struct Interpreter {
     vm: VM,
-    compiler: Compiler,
+    session: Session,
+    diagnostics: Sink,
 }
 
 impl Interpreter {
     pub fn new() -> Self {
+        Interpreter::with_config(VMConfig::default())
+    }
+
+    pub fn with_config(config: VMConfig) -> Self {
         Interpreter {
-            vm: VM::new(),
-            compiler: Compiler::new(),
+            vm: VM::with_config(config),
+            session: Session::new(),
+            diagnostics: Sink::stderr(),
         }
     }
 
-    pub fn run_file(&mut self, path: &str) {
-        let content: String = String::from_utf8_lossy(
-            &fs::read(Path::new(path)).expect(&format!("Path not found: {}", path)),
-        )
-        .parse()
-        .expect("Unable to parse source file");
+    /// Redirects the output of `print` statements.
+    pub fn set_output(&mut self, output: Sink) {
+        self.vm.set_output(output);
+    }
+
+    /// Redirects compile and runtime errors reported by `run_file` and `repl`.
+    pub fn set_diagnostics(&mut self, diagnostics: Sink) {
+        self.session.compiler().set_diagnostics(diagnostics.clone());
+        self.diagnostics = diagnostics;
+    }
+
+    /// Sets the optimisations applied to compiled source.
+    pub fn set_opt_level(&mut self, opt_level: OptLevel) {
+        self.session.compiler().set_opt_level(opt_level);
+    }
+
+    /// Compiles source in a single pass, without building a syntax tree first.
+    pub fn set_single_pass(&mut self, single_pass: bool) {
+        self.session.compiler().set_single_pass(single_pass);
+    }
+
+    /// Reports the lints of `config` for compiled source, `None` turns them off.
+    pub fn set_lints(&mut self, config: Option<LintConfig>) {
+        self.session.compiler().set_lints(config);
+    }
 
-        self.interpret(&content);
+    /// Redirects the warnings of the lints, which go to stderr by default.
+    pub fn set_warnings(&mut self, warnings: Sink) {
+        self.session.compiler().set_warnings(warnings);
     }
 
+    /// Writes every executed instruction and the stack to the output.
+    pub fn set_trace(&mut self, trace: bool) {
+        self.vm.set_trace(trace);
+    }
+
+    /// Runs a script file, either kentauri source or a chunk compiled with `save_bytecode`.
+    /// A path of `-` reads the script from stdin.
+    pub fn run_file(&mut self, path: &str) -> InterpreterResult<()> {
+        let bytes = read_input(path)?;
+
+        self.run(&bytes)
+    }
+
+    /// Runs kentauri source or serialized bytecode.
+    pub fn run(&mut self, bytes: &[u8]) -> InterpreterResult<()> {
+        let chunk = self.load(bytes)?;
+
+        self.execute(chunk)
+    }
+
+    /// Compiles kentauri source, or deserializes and verifies bytecode, without running it.
+    pub fn load(&mut self, bytes: &[u8]) -> InterpreterResult<Chunk> {
+        if binary::is_bytecode(bytes) {
+            return binary::deserialize(bytes)
+                .map_err(|e| InterpreterError::BytecodeError(Error::message(&e.to_string())));
+        }
+
+        self.session.compile(&String::from_utf8_lossy(bytes))
+    }
+
+    /// Loads source or bytecode like `load` and decodes the resulting chunk.
+    pub fn disassemble(&mut self, bytes: &[u8], name: &str) -> InterpreterResult<Disassembly> {
+        let chunk = self.load(bytes)?;
+
+        Disassembly::new(&chunk, name)
+            .map_err(|e| InterpreterError::BytecodeError(Error::message(&e.to_string())))
+    }
+
+    /// Formats kentauri source in the canonical style, see `formatter::format`. Syntax
+    /// errors are written to the diagnostics like the compiler does.
+    pub fn format(&mut self, bytes: &[u8]) -> InterpreterResult<String> {
+        formatter::format(&String::from_utf8_lossy(bytes)).map_err(|mut errors| {
+            for error in errors.iter() {
+                let _ = writeln!(self.diagnostics, "{}", error);
+            }
+            InterpreterError::CompilerError(errors.pop().unwrap())
+        })
+    }
+
+    /// Writes `chunk` to `output` in the binary bytecode format.
+    pub fn save_bytecode(&self, chunk: &Chunk, output: &str) -> InterpreterResult<()> {
+        let bytes = binary::serialize(chunk)
+            .map_err(|e| InterpreterError::BytecodeError(Error::message(&e.to_string())))?;
+
+        write_output(output, &bytes)
+    }
+
+    /// Starts an interactive session on stdin, keeping history in `~/.kentauri_history`.
     pub fn repl(&mut self) {
         let stdin = io::stdin();
-        self.compiler = Compiler::new();
-
-        loop {
-            let mut buffer = String::new();
-            print!("> ");
-            io::stdout().flush().unwrap();
-            stdin
-                .read_line(&mut buffer)
-                .expect("Unexpected error on reading input");
-            match self.interpret(&buffer) {
-                Err(e) => println!("{}", e),
-                Ok(_) => (),
-            }
+        let mut repl = Repl::new(self);
+        repl.set_history_file(Repl::default_history_file());
+
+        repl.run(stdin.lock());
+    }
+
+    /// Shows the value of a trailing expression without `;`, see `Compiler::set_echo`.
+    pub fn set_echo(&mut self, echo: bool) {
+        self.session.compiler().set_echo(echo);
+    }
+
+    /// Global variables sorted by name.
+    pub fn globals(&self) -> Vec<(String, Value)> {
+        debugger::globals(&self.vm)
+    }
+
+    /// Forgets all globals, as if the interpreter was just created.
+    pub fn reset(&mut self) {
+        self.vm.reset();
+        self.session.reset();
+    }
+
+    /// Everything the compiled inputs declared so far.
+    pub fn compilation(&self) -> &Compilation {
+        self.session.compilation()
+    }
+
+    /// Limits each run to `fuel` more instructions, `None` removes the limit. A run that
+    /// exhausts it fails with `InterpreterError::OutOfFuel` and can be continued with
+    /// `add_fuel` and `resume`.
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.vm.set_fuel(fuel);
+    }
+
+    pub fn add_fuel(&mut self, fuel: u64) {
+        self.vm.add_fuel(fuel);
+    }
+
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.vm.interrupt_handle()
+    }
+
+    /// Continues a run suspended by `InterpreterError::OutOfFuel` or
+    /// `InterpreterError::Interrupted`.
+    pub fn resume(&mut self) -> InterpreterResult<()> {
+        self.vm.resume().map(|_| ()).map_err(InterpreterError::from)
+    }
+
+    /// Runs `chunk` with `hook` called before every instruction, see `VM::interpret_with`.
+    pub fn execute_with<H: DebugHook>(
+        &mut self,
+        chunk: Chunk,
+        hook: &mut H,
+    ) -> InterpreterResult<()> {
+        self.vm
+            .interpret_with(chunk, hook)
+            .map(|_| ())
+            .map_err(InterpreterError::from)
+    }
+
+    pub fn interpret(&mut self, source: &str) -> InterpreterResult<()> {
+        let chunk = self.session.compile(source)?;
+
+        self.execute(chunk)
+    }
+
+    /// Writes `e` to the diagnostics sink, unless it was already reported while compiling.
+    pub fn report(&mut self, e: &InterpreterError) {
+        // The compiler reports every compile error to the diagnostics as it finds them.
+        if let InterpreterError::CompilerError(_) = e {
+            return;
         }
+
+        let _ = writeln!(self.diagnostics, "{}", e);
+    }
+
+    fn execute(&mut self, chunk: Chunk) -> InterpreterResult<()> {
+        let result = self.vm.interpret(chunk);
+
+        result.map_err(InterpreterError::from).map(|_| ())
+    }
+}
+
+/// Reads a script, `-` stands for stdin.
+pub fn read_input(path: &str) -> InterpreterResult<Vec<u8>> {
+    if path == "-" {
+        let mut bytes = Vec::new();
+        io::stdin()
+            .read_to_end(&mut bytes)
+            .map_err(|e| io_error("<stdin>", e))?;
+        return Ok(bytes);
+    }
+
+    fs::read(Path::new(path)).map_err(|e| io_error(path, e))
+}
+
+/// Writes a file, reporting failures like `read_input`.
+pub fn write_output(path: &str, bytes: &[u8]) -> InterpreterResult<()> {
+    fs::write(Path::new(path), bytes).map_err(|e| io_error(path, e))
+}
+
+fn io_error(path: &str, e: io::Error) -> InterpreterError {
+    InterpreterError::IoError(Error::message(&format!("{}: {}", path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Interpreter;
+    use crate::compiler::lint::LintConfig;
+    use crate::error::interpreter::InterpreterError;
+    use crate::output::sink::OutputBuffer;
+
+    #[test]
+    fn test_captured_output() {
+        let output = OutputBuffer::new();
+        let diagnostics = OutputBuffer::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_output(output.sink());
+        interpreter.set_diagnostics(diagnostics.sink());
+
+        assert!(interpreter
+            .interpret("var a = 1; print a + 2; print \"b\";")
+            .is_ok());
+        assert_eq!("3\nb\n", output.take());
+
+        assert!(interpreter.interpret("print ;").is_err());
+        assert_eq!("", output.contents());
+        assert!(diagnostics.take().contains("Expect expression"));
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let mut interpreter = Interpreter::new();
+
+        match interpreter.run_file("does/not/exist.kt") {
+            Err(e @ InterpreterError::IoError(_)) => assert_eq!(74, e.exit_code()),
+            _ => panic!("missing file not reported"),
+        }
+    }
+
+    #[test]
+    fn test_long_constants() {
+        let output = OutputBuffer::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_output(output.sink());
+
+        let terms: Vec<String> = (0..300).map(|n| n.to_string()).collect();
+        let source = format!("print {};", terms.join(" + "));
+        assert!(interpreter.interpret(&source).is_ok());
+        assert_eq!("44850\n", output.take());
     }
 
-    fn interpret(&mut self, source: &str) -> InterpreterResult<()> {
-        let mut compilation = self.compiler.compile(source)?;
-        println!("{:?}", compilation.chunk.as_ref().unwrap().code);
+    #[test]
+    fn test_warnings() {
+        let diagnostics = OutputBuffer::new();
+        let warnings = OutputBuffer::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_output(OutputBuffer::new().sink());
+        interpreter.set_diagnostics(diagnostics.sink());
+        interpreter.set_warnings(warnings.sink());
 
-        let result = self.vm.interpret(compilation.chunk.take().unwrap());
+        assert!(interpreter.interpret("{ var a = 1; }").is_ok());
+        assert_eq!("", warnings.contents());
 
-        result
-            .map_err(|e| InterpreterError::RuntimeError(e))
-            .map(|c| ())
+        interpreter.set_lints(Some(LintConfig::new()));
+        assert!(interpreter.interpret("{ var a = 1; }").is_ok());
+        assert_eq!(
+            "[line 1] Warning at 'a': Local variable 'a' is never read (unused_variable)\n",
+            warnings.take()
+        );
+        assert_eq!("", diagnostics.contents());
     }
 }
@@ -1 +1,2 @@
 pub mod interpreter;
+pub mod repl;
@@ -0,0 +1,323 @@
+use crate::interpreter::interpreter::Interpreter;
+use crate::output::sink::Sink;
+use crate::scanner::scanner::Scanner;
+use crate::scanner::token::TokenType;
+use std::env;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = "... ";
+
+const HISTORY_FILE: &str = ".kentauri_history";
+const HISTORY_LIMIT: usize = 1000;
+
+const HELP: &str = ":help            Show this message
+:globals         List the global variables and where they were declared
+:disasm <code>   Print the bytecode of <code>
+:reset           Forget all globals
+:load <path>     Run a script in this session
+:history         List previous entries
+:quit            Leave the session, same as Ctrl-D";
+
+enum Flow {
+    Continue,
+    Quit,
+}
+
+/// Interactive session over an `Interpreter`. Entries spanning several lines are collected
+/// until their braces, parentheses and strings are closed.
+pub struct Repl<'a> {
+    interpreter: &'a mut Interpreter,
+    output: Sink,
+    history: Vec<String>,
+    history_file: Option<PathBuf>,
+}
+
+impl<'a> Repl<'a> {
+    pub fn new(interpreter: &'a mut Interpreter) -> Self {
+        Repl {
+            interpreter,
+            output: Sink::stdout(),
+            history: Vec::new(),
+            history_file: None,
+        }
+    }
+
+    pub fn default_history_file() -> Option<PathBuf> {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE))
+    }
+
+    /// Redirects prompts and the output of meta-commands.
+    pub fn set_output(&mut self, output: Sink) {
+        self.output = output;
+    }
+
+    /// Loads the previous entries from `path` and appends new entries to it.
+    pub fn set_history_file(&mut self, path: Option<PathBuf>) {
+        if let Some(contents) = path.as_ref().and_then(|p| fs::read_to_string(p).ok()) {
+            self.history = contents.lines().map(unescape).collect();
+
+            if self.history.len() > HISTORY_LIMIT {
+                self.history.drain(..self.history.len() - HISTORY_LIMIT);
+                let contents: String = self
+                    .history
+                    .iter()
+                    .map(|entry| escape(entry) + "\n")
+                    .collect();
+                let _ = fs::write(path.as_ref().unwrap(), contents);
+            }
+        }
+
+        self.history_file = path;
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Reads and evaluates entries until `:quit` or the end of `input`.
+    pub fn run(&mut self, mut input: impl BufRead) {
+        self.interpreter.set_echo(true);
+
+        while let Some(entry) = self.read_entry(&mut input) {
+            let entry = entry.trim_end();
+            if entry.trim().is_empty() {
+                continue;
+            }
+
+            self.remember(entry);
+
+            let flow = if entry.trim_start().starts_with(':') {
+                self.command(entry.trim())
+            } else {
+                if let Err(e) = self.interpreter.interpret(entry) {
+                    self.interpreter.report(&e);
+                }
+                Flow::Continue
+            };
+
+            if let Flow::Quit = flow {
+                break;
+            }
+        }
+
+        self.interpreter.set_echo(false);
+    }
+
+    fn read_entry(&mut self, input: &mut impl BufRead) -> Option<String> {
+        let mut entry = String::new();
+
+        loop {
+            let prompt = if entry.is_empty() {
+                PROMPT
+            } else {
+                CONTINUATION_PROMPT
+            };
+            let _ = write!(self.output, "{}", prompt);
+            let _ = self.output.flush();
+
+            let mut line = String::new();
+            match input.read_line(&mut line) {
+                Ok(0) | Err(_) => {
+                    let _ = writeln!(self.output);
+                    return if entry.trim().is_empty() {
+                        None
+                    } else {
+                        Some(entry)
+                    };
+                }
+                Ok(_) => entry.push_str(&line),
+            }
+
+            if entry.trim_start().starts_with(':') || !is_incomplete(&entry) {
+                return Some(entry);
+            }
+        }
+    }
+
+    fn command(&mut self, entry: &str) -> Flow {
+        let (name, argument) = match entry.find(char::is_whitespace) {
+            Some(i) => (&entry[..i], entry[i..].trim()),
+            None => (entry, ""),
+        };
+
+        match name {
+            ":help" => {
+                let _ = writeln!(self.output, "{}", HELP);
+            }
+            ":quit" => return Flow::Quit,
+            ":globals" => {
+                for (name, value) in self.interpreter.globals() {
+                    let _ = write!(self.output, "{} = {}", name, value.repr());
+                    if let Some(definition) = self.interpreter.compilation().definition(&name) {
+                        let _ = write!(
+                            self.output,
+                            "  (entry {}, line {})",
+                            definition.input + 1,
+                            definition.line
+                        );
+                    }
+                    let _ = writeln!(self.output);
+                }
+            }
+            ":reset" => self.interpreter.reset(),
+            ":load" if !argument.is_empty() => {
+                self.interpreter.set_echo(false);
+                if let Err(e) = self.interpreter.run_file(argument) {
+                    self.interpreter.report(&e);
+                }
+                self.interpreter.set_echo(true);
+            }
+            ":disasm" if !argument.is_empty() => {
+                let mut source = argument.to_string();
+                if !source.ends_with(';') && !source.ends_with('}') {
+                    source.push(';');
+                }
+
+                match self.interpreter.disassemble(source.as_bytes(), argument) {
+                    Ok(disassembly) => {
+                        let _ = write!(self.output, "{}", disassembly);
+                    }
+                    Err(e) => self.interpreter.report(&e),
+                }
+            }
+            ":history" => {
+                for (i, entry) in self.history.iter().enumerate() {
+                    let _ = writeln!(self.output, "{:>4}  {}", i + 1, entry);
+                }
+            }
+            _ => {
+                let _ = writeln!(
+                    self.output,
+                    "Unknown command {}, :help lists the commands",
+                    entry
+                );
+            }
+        }
+
+        Flow::Continue
+    }
+
+    fn remember(&mut self, entry: &str) {
+        if self.history.len() == HISTORY_LIMIT {
+            self.history.remove(0);
+        }
+        self.history.push(entry.to_string());
+
+        if let Some(path) = &self.history_file {
+            if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+                let _ = writeln!(file, "{}", escape(entry));
+            }
+        }
+    }
+}
+
+/// Whether `source` ends inside a block, a parenthesized expression or a string, so more
+/// lines are needed before it can be compiled.
+pub fn is_incomplete(source: &str) -> bool {
+    let mut scanner = Scanner::new(source);
+    let mut depth: i64 = 0;
+
+    loop {
+        let token = scanner.scan_token();
+        match token.token_type {
+            TokenType::LEFT_PAREN | TokenType::LEFT_BRACE => depth += 1,
+            TokenType::RIGHT_PAREN | TokenType::RIGHT_BRACE => depth -= 1,
+            TokenType::ERROR if token.lexem == "Unterminated string" => return true,
+            TokenType::EOF => return depth > 0,
+            _ => (),
+        }
+    }
+}
+
+// History entries are stored one per line, so the newlines of multi-line entries are escaped.
+fn escape(entry: &str) -> String {
+    entry.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(line: &str) -> String {
+    let mut entry = String::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                entry.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                entry.push('\\');
+                chars.next();
+            }
+            _ => entry.push(c),
+        }
+    }
+
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape, is_incomplete, unescape, Repl};
+    use crate::interpreter::interpreter::Interpreter;
+    use crate::output::sink::OutputBuffer;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_incomplete_input() {
+        assert!(is_incomplete("{"));
+        assert!(is_incomplete("{ print (1 +"));
+        assert!(is_incomplete("print \"abc"));
+        assert!(!is_incomplete("{ print 1; }"));
+        assert!(!is_incomplete("print 8 / 2;"));
+        assert!(!is_incomplete("}"));
+        assert!(!is_incomplete("print \"{\"; // {"));
+    }
+
+    #[test]
+    fn test_session() {
+        let output = OutputBuffer::new();
+        let prompts = OutputBuffer::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_output(output.sink());
+        interpreter.set_diagnostics(OutputBuffer::new().sink());
+
+        let input = "var a = 1;\n{\n  var b = a + 1;\n  print b;\n}\n:globals\n:reset\n:globals\n\
+                     print \"x\ny\";\n:quit\nprint 3;\n";
+        let mut repl = Repl::new(&mut interpreter);
+        repl.set_output(prompts.sink());
+        repl.run(Cursor::new(input));
+
+        assert_eq!("2\nx\ny\n", output.take());
+        assert_eq!(1, prompts.contents().matches("a = 1").count());
+        assert!(prompts.contents().contains("a = 1  (entry 1, line 1)\n"));
+        assert_eq!(4, prompts.contents().matches("... ").count());
+        assert_eq!("{\n  var b = a + 1;\n  print b;\n}", repl.history()[1]);
+        assert_eq!(":quit", repl.history().last().unwrap());
+    }
+
+    #[test]
+    fn test_echo() {
+        let output = OutputBuffer::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_output(output.sink());
+        interpreter.set_diagnostics(OutputBuffer::new().sink());
+
+        let input = "1 + 2\n_ * 2\n\"a\" + \"b\"\nprint 4;\n5;\n{ 6 }\nnil\n";
+        Repl::new(&mut interpreter).run(Cursor::new(input));
+
+        assert_eq!("3\n6\n<string> \"ab\"\n4\nnil\n", output.take());
+        assert!(interpreter.interpret("7").is_err());
+    }
+
+    #[test]
+    fn test_history_escaping() {
+        let entry = "print \"a\\n\";\nprint 1;";
+
+        assert_eq!(1, escape(entry).lines().count());
+        assert_eq!(entry, unescape(&escape(entry)));
+    }
+}
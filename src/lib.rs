@@ -1,10 +1,18 @@
+#![allow(clippy::module_inception, clippy::new_without_default)]
+
 pub mod bytecode;
 #[macro_use]
 pub mod debug;
 pub mod compiler;
+pub mod dap;
 pub mod error;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
 pub mod interpreter;
+pub mod lsp;
+pub mod output;
 pub mod scanner;
+pub mod syntax;
 pub mod util;
 pub mod value;
 pub mod vm;
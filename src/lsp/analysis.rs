@@ -0,0 +1,350 @@
+use crate::compiler::codegen::CodeGenerator;
+use crate::compiler::lint::{lint, LintConfig, Warning};
+use crate::compiler::scope::ScopeTracker;
+use crate::error::error::Error;
+use crate::scanner::scanner::Scanner;
+use crate::scanner::token::{Token, TokenType};
+use crate::syntax::ast::{Expr, ExprKind, Identifier, Program, Stmt, StmtKind};
+use crate::syntax::parser::Parser;
+use crate::syntax::span::Span;
+use std::collections::HashMap;
+
+/// A variable declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub name: Identifier,
+    /// The whole `var` statement.
+    pub statement: Span,
+    pub global: bool,
+}
+
+/// An occurrence of a variable name, declarations included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub span: Span,
+    /// Index of the declaration the name resolves to, `None` for undeclared globals.
+    pub declaration: Option<usize>,
+}
+
+/// Kinds of the tokens reported for highlighting, in the order of `TOKEN_TYPES`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TokenKind {
+    Keyword,
+    Variable,
+    String,
+    Number,
+    Operator,
+    Comment,
+}
+
+pub const TOKEN_TYPES: [&str; 6] = [
+    "keyword", "variable", "string", "number", "operator", "comment",
+];
+
+/// Everything the language server knows about a source: its errors and warnings and how
+/// its variable names resolve.
+pub struct Analysis {
+    pub program: Program,
+    pub errors: Vec<Error>,
+    pub warnings: Vec<Warning>,
+    pub declarations: Vec<Declaration>,
+    pub references: Vec<Reference>,
+}
+
+impl Analysis {
+    /// Compiles `source` to collect every error, as far as the compiler gets: the syntax
+    /// errors, or the errors about names if there are none, or the lint warnings if it
+    /// compiles.
+    pub fn new(source: &str) -> Self {
+        let mut parser = Parser::new(source);
+        parser.set_keep_comments(true);
+        let (program, mut errors) = parser.parse();
+
+        let mut warnings = Vec::new();
+        if errors.is_empty() {
+            match CodeGenerator::new().generate(&program) {
+                Ok(_) => warnings = lint(&program, &LintConfig::new()),
+                Err(generated) => errors = generated,
+            }
+        }
+
+        let mut resolver = Resolver {
+            scope: ScopeTracker::new(),
+            locals: Vec::new(),
+            declarations: Vec::new(),
+            references: Vec::new(),
+            globals: Vec::new(),
+        };
+        for statement in program.statements.iter() {
+            resolver.statement(statement);
+        }
+        resolver.resolve_globals();
+
+        Analysis {
+            program,
+            errors,
+            warnings,
+            declarations: resolver.declarations,
+            references: resolver.references,
+        }
+    }
+
+    /// The reference at `offset`, which may also be just past its end.
+    pub fn reference_at(&self, offset: usize) -> Option<&Reference> {
+        self.references
+            .iter()
+            .find(|reference| reference.span.start <= offset && offset <= reference.span.end)
+    }
+
+    pub fn declaration_at(&self, offset: usize) -> Option<&Declaration> {
+        let index = self.reference_at(offset)?.declaration?;
+        self.declarations.get(index)
+    }
+}
+
+/// The tokens of `source` to highlight, with the comments, in source order.
+pub fn highlighted_tokens(source: &str) -> Vec<(Span, TokenKind)> {
+    let mut scanner = Scanner::new(source);
+    scanner.set_keep_comments(true);
+
+    let mut tokens = Vec::new();
+    loop {
+        let token = scanner.scan_token();
+        if token.token_type == TokenType::EOF {
+            break;
+        }
+        if let Some(kind) = token_kind(&token) {
+            tokens.push((token.span, kind));
+        }
+    }
+
+    tokens.extend(
+        scanner
+            .take_comments()
+            .into_iter()
+            .map(|comment| (comment.span, TokenKind::Comment)),
+    );
+    tokens.sort_by_key(|(span, _)| span.start);
+
+    tokens
+}
+
+fn token_kind(token: &Token) -> Option<TokenKind> {
+    let kind = match token.token_type {
+        TokenType::IDENTIFIER => TokenKind::Variable,
+        TokenType::STRING => TokenKind::String,
+        TokenType::NUMBER => TokenKind::Number,
+        TokenType::MINUS
+        | TokenType::PLUS
+        | TokenType::SLASH
+        | TokenType::STAR
+        | TokenType::BANG
+        | TokenType::BANG_EQUAL
+        | TokenType::EQUAL
+        | TokenType::EQUAL_EQUAL
+        | TokenType::GREATER
+        | TokenType::GREATER_EQUAL
+        | TokenType::LESS
+        | TokenType::LESS_EQUAL => TokenKind::Operator,
+        TokenType::AND
+        | TokenType::CLASS
+        | TokenType::ELSE
+        | TokenType::FALSE
+        | TokenType::FOR
+        | TokenType::FUN
+        | TokenType::IF
+        | TokenType::NIL
+        | TokenType::OR
+        | TokenType::PRINT
+        | TokenType::RETURN
+        | TokenType::SUPER
+        | TokenType::THIS
+        | TokenType::TRUE
+        | TokenType::VAR
+        | TokenType::WHILE => TokenKind::Keyword,
+        _ => return None,
+    };
+
+    Some(kind)
+}
+
+/// Resolves names with the scoping rules of the compiler. Globals are bound when a
+/// statement runs, so a global name refers to its last declaration before it, or to the
+/// first one if it is used before any.
+struct Resolver {
+    scope: ScopeTracker,
+    // Declaration index of each local, indexed like the stack slots of the scope tracker.
+    locals: Vec<usize>,
+    declarations: Vec<Declaration>,
+    references: Vec<Reference>,
+    // References to globals, with the name, resolved once every declaration is known.
+    globals: Vec<(usize, String)>,
+}
+
+impl Resolver {
+    fn statement(&mut self, statement: &Stmt) {
+        match &statement.kind {
+            StmtKind::Var { name, initializer } => {
+                if let Some(initializer) = initializer {
+                    self.expression(initializer);
+                }
+
+                let global = self.scope.is_global();
+                self.declarations.push(Declaration {
+                    name: name.clone(),
+                    statement: statement.span,
+                    global,
+                });
+                let index = self.declarations.len() - 1;
+                self.references.push(Reference {
+                    span: name.span,
+                    declaration: Some(index),
+                });
+
+                if !global {
+                    let mut token = Token::new(TokenType::IDENTIFIER, name.name.clone(), name.line);
+                    token.span = name.span;
+                    // The compiler reports scopes with too many locals.
+                    let _ = self.scope.add_local(token);
+                    self.scope.define_last();
+                    self.locals.push(index);
+                }
+            }
+            StmtKind::Print(expr) | StmtKind::Expression(expr) | StmtKind::Echo(expr) => {
+                self.expression(expr)
+            }
+            StmtKind::Block(statements) => {
+                self.scope.begin();
+                for statement in statements.iter() {
+                    self.statement(statement);
+                }
+
+                let count = self.scope.end() as usize;
+                self.locals.truncate(self.locals.len() - count);
+            }
+        }
+    }
+
+    fn expression(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Variable(name) => self.reference(name),
+            ExprKind::Assign { name, value } => {
+                self.reference(name);
+                self.expression(value);
+            }
+            ExprKind::Unary { operand, .. } => self.expression(operand),
+            ExprKind::Binary { left, right, .. } => {
+                self.expression(left);
+                self.expression(right);
+            }
+            ExprKind::Grouping(expr) => self.expression(expr),
+            ExprKind::Number(_)
+            | ExprKind::String(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Nil
+            | ExprKind::Invalid => (),
+        }
+    }
+
+    fn reference(&mut self, name: &Identifier) {
+        let local = self.scope.locate_local(|local| local == name.name);
+        let declaration = local.map(|(slot, _)| self.locals[slot]);
+        if declaration.is_none() {
+            self.globals
+                .push((self.references.len(), name.name.clone()));
+        }
+
+        self.references.push(Reference {
+            span: name.span,
+            declaration,
+        });
+    }
+
+    fn resolve_globals(&mut self) {
+        let mut globals: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, declaration) in self.declarations.iter().enumerate() {
+            if declaration.global {
+                globals
+                    .entry(&declaration.name.name)
+                    .or_default()
+                    .push(index);
+            }
+        }
+
+        for (reference, name) in self.globals.iter() {
+            let candidates = match globals.get(name.as_str()) {
+                Some(candidates) => candidates,
+                None => continue,
+            };
+
+            let start = self.references[*reference].span.start;
+            let declaration = candidates
+                .iter()
+                .rev()
+                .find(|index| self.declarations[**index].name.span.start < start)
+                .or_else(|| candidates.first());
+            self.references[*reference].declaration = declaration.copied();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{highlighted_tokens, Analysis, TokenKind};
+
+    #[test]
+    fn test_resolution() {
+        let source =
+            "print a;\nvar a = 1;\n{ var b = a; var a = b; { print a; } }\nvar a = 2;\na = 3;";
+        let analysis = Analysis::new(source);
+        assert!(analysis.errors.is_empty());
+        assert_eq!(4, analysis.declarations.len());
+
+        let line_of = |offset: usize| analysis.declaration_at(offset).map(|d| d.name.line);
+        // Used before its declaration, the global resolves to the first one.
+        assert_eq!(Some(2), line_of(6));
+        // Before the local is declared, the global.
+        assert_eq!(Some(2), line_of(30));
+        // Inside the nested block, the local.
+        assert_eq!(Some(3), line_of(52));
+        assert!(!analysis.declarations[2].global);
+        // After the redeclaration, the last global.
+        assert_eq!(Some(4), line_of(70));
+        assert_eq!(None, line_of(8));
+    }
+
+    #[test]
+    fn test_errors_and_warnings() {
+        let analysis = Analysis::new("print ;\nvar = 1;");
+        assert_eq!(2, analysis.errors.len());
+
+        let analysis = Analysis::new("{ var a; var a; }");
+        assert_eq!(1, analysis.errors.len());
+
+        let analysis = Analysis::new("{ var a; }");
+        assert!(analysis.errors.is_empty());
+        assert_eq!(1, analysis.warnings.len());
+    }
+
+    #[test]
+    fn test_highlighted_tokens() {
+        let kinds: Vec<TokenKind> = highlighted_tokens("var a = \"s\"; // c\nprint -1;")
+            .into_iter()
+            .map(|(_, kind)| kind)
+            .collect();
+
+        assert_eq!(
+            vec![
+                TokenKind::Keyword,
+                TokenKind::Variable,
+                TokenKind::Operator,
+                TokenKind::String,
+                TokenKind::Comment,
+                TokenKind::Keyword,
+                TokenKind::Operator,
+                TokenKind::Number,
+            ],
+            kinds
+        );
+    }
+}
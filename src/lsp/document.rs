@@ -0,0 +1,195 @@
+use crate::lsp::analysis::{highlighted_tokens, Analysis};
+use crate::syntax::span::Span;
+use crate::util::json::Json;
+
+/// An open document. Spans count characters while the protocol counts lines and UTF-16
+/// code units within them, so positions are converted here.
+pub struct Document {
+    pub text: String,
+    chars: Vec<char>,
+    // Offset of the first character of each line.
+    line_starts: Vec<usize>,
+    pub analysis: Analysis,
+}
+
+impl Document {
+    pub fn new(text: String) -> Self {
+        let chars: Vec<char> = text.chars().collect();
+        let mut line_starts = vec![0];
+        for (offset, c) in chars.iter().enumerate() {
+            if *c == '\n' {
+                line_starts.push(offset + 1);
+            }
+        }
+
+        let analysis = Analysis::new(&text);
+        Document {
+            text,
+            chars,
+            line_starts,
+            analysis,
+        }
+    }
+
+    /// The zero based line and UTF-16 column of the character offset `offset`.
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.chars.len());
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+
+        let column = self.chars[self.line_starts[line]..offset]
+            .iter()
+            .map(|c| c.len_utf16())
+            .sum();
+        (line, column)
+    }
+
+    /// The character offset of a position, clamped to the line and to the document.
+    pub fn offset(&self, line: usize, column: usize) -> usize {
+        let start = match self.line_starts.get(line) {
+            Some(start) => *start,
+            None => return self.chars.len(),
+        };
+
+        let mut offset = start;
+        let mut units = 0;
+        while offset < self.chars.len() && self.chars[offset] != '\n' {
+            units += self.chars[offset].len_utf16();
+            if units > column {
+                break;
+            }
+            offset += 1;
+        }
+        offset
+    }
+
+    pub fn text_of(&self, span: Span) -> String {
+        self.chars[span.start..span.end].iter().collect()
+    }
+
+    pub fn range(&self, span: Span) -> Json {
+        Json::object(vec![
+            ("start", self.json_position(span.start)),
+            ("end", self.json_position(span.end)),
+        ])
+    }
+
+    pub fn whole_range(&self) -> Json {
+        self.range(Span::new(0, self.chars.len()))
+    }
+
+    fn json_position(&self, offset: usize) -> Json {
+        let (line, character) = self.position(offset);
+        Json::object(vec![
+            ("line", Json::from(line)),
+            ("character", Json::from(character)),
+        ])
+    }
+
+    /// The errors and warnings, as protocol diagnostics.
+    pub fn diagnostics(&self) -> Vec<Json> {
+        let errors = self.analysis.errors.iter().map(|error| {
+            let range = match error.token() {
+                Some(token) => self.range(token.span),
+                None => self.whole_range(),
+            };
+            diagnostic(range, 1, error.description().to_string())
+        });
+
+        let warnings = self.analysis.warnings.iter().map(|warning| {
+            let message = format!("{} ({})", warning.message, warning.lint.name());
+            diagnostic(self.range(warning.span), 2, message)
+        });
+
+        errors.chain(warnings).collect()
+    }
+
+    /// The highlighted tokens, encoded relative to each other as the protocol wants them.
+    /// Tokens spanning several lines, like strings, are split into one token per line.
+    pub fn semantic_tokens(&self) -> Vec<usize> {
+        let mut data = Vec::new();
+        let (mut last_line, mut last_column) = (0, 0);
+
+        for (span, kind) in highlighted_tokens(&self.text) {
+            let mut start = span.start;
+            while start < span.end {
+                let end = self.chars[start..span.end]
+                    .iter()
+                    .position(|c| *c == '\n')
+                    .map_or(span.end, |i| start + i);
+
+                let (line, column) = self.position(start);
+                let length: usize = self.chars[start..end].iter().map(|c| c.len_utf16()).sum();
+                if length > 0 {
+                    let delta_column = if line == last_line {
+                        column - last_column
+                    } else {
+                        column
+                    };
+                    data.extend_from_slice(&[
+                        line - last_line,
+                        delta_column,
+                        length,
+                        kind as usize,
+                        0,
+                    ]);
+                    last_line = line;
+                    last_column = column;
+                }
+
+                start = end + 1;
+            }
+        }
+
+        data
+    }
+}
+
+fn diagnostic(range: Json, severity: usize, message: String) -> Json {
+    Json::object(vec![
+        ("range", range),
+        ("severity", Json::from(severity)),
+        ("source", Json::from("kentauri")),
+        ("message", Json::from(message)),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Document;
+
+    #[test]
+    fn test_positions() {
+        let document = Document::new(String::from("print \"\u{1F600}\";\nvar a;"));
+
+        assert_eq!((0, 0), document.position(0));
+        // The emoji takes two UTF-16 code units.
+        assert_eq!((0, 10), document.position(9));
+        assert_eq!((1, 3), document.position(14));
+        assert_eq!((1, 6), document.position(100));
+
+        assert_eq!(9, document.offset(0, 10));
+        assert_eq!(15, document.offset(1, 4));
+        assert_eq!(10, document.offset(0, 99));
+        assert_eq!(17, document.offset(5, 0));
+    }
+
+    #[test]
+    fn test_semantic_tokens() {
+        let document = Document::new(String::from("var s = \"a\nbc\"; // x"));
+
+        assert_eq!(
+            vec![
+                0, 0, 3, 0, 0, // var
+                0, 4, 1, 1, 0, // s
+                0, 2, 1, 4, 0, // =
+                0, 2, 2, 2, 0, // "a
+                1, 0, 3, 2, 0, // bc"
+                0, 5, 4, 5, 0, // // x
+            ],
+            document.semantic_tokens()
+        );
+    }
+}
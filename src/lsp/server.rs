@@ -0,0 +1,407 @@
+use crate::lsp::analysis::TOKEN_TYPES;
+use crate::lsp::document::Document;
+use crate::syntax::formatter::format;
+use crate::util::json::Json;
+use crate::util::rpc::{read_message, write_message};
+use std::collections::HashMap;
+use std::io;
+use std::io::{BufRead, Write};
+
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+const SERVER_NOT_INITIALIZED: i32 = -32002;
+
+// Kind of the variables in document symbols.
+const SYMBOL_VARIABLE: usize = 13;
+// Documents are sent whole on every change.
+const SYNC_FULL: usize = 1;
+
+struct ResponseError {
+    code: i32,
+    message: String,
+}
+
+type Response = Result<Json, ResponseError>;
+
+fn error(code: i32, message: &str) -> ResponseError {
+    ResponseError {
+        code,
+        message: message.to_string(),
+    }
+}
+
+/// Serves the messages read from `reader` until the client asks to exit, and returns the
+/// exit code: 0 after a shutdown request, 1 otherwise, as when the input ends first.
+pub fn run<R: BufRead, W: Write>(mut reader: R, writer: W) -> io::Result<i32> {
+    let mut server = Server::new(writer);
+
+    while let Some(message) = read_message(&mut reader)? {
+        let exit = match message {
+            Ok(message) => server.handle(&message)?,
+            Err(e) => {
+                server.respond(&Json::Null, Err(error(PARSE_ERROR, &e)))?;
+                None
+            }
+        };
+
+        if let Some(code) = exit {
+            return Ok(code);
+        }
+    }
+
+    Ok(1)
+}
+
+pub struct Server<W: Write> {
+    writer: W,
+    documents: HashMap<String, Document>,
+    initialized: bool,
+    shutdown: bool,
+}
+
+impl<W: Write> Server<W> {
+    pub fn new(writer: W) -> Self {
+        Server {
+            writer,
+            documents: HashMap::new(),
+            initialized: false,
+            shutdown: false,
+        }
+    }
+
+    /// Handles a request or a notification, and returns the exit code once asked to exit.
+    pub fn handle(&mut self, message: &Json) -> io::Result<Option<i32>> {
+        let method = match message.get("method").as_str() {
+            Some(method) => method,
+            // A response to a request of ours, the server sends none.
+            None => return Ok(None),
+        };
+        let params = message.get("params");
+
+        let id = message.get("id");
+        if !id.is_null() {
+            let response = self.request(method, params);
+            self.respond(id, response)?;
+            return Ok(None);
+        }
+
+        match method {
+            "exit" => return Ok(Some(if self.shutdown { 0 } else { 1 })),
+            "textDocument/didOpen" => {
+                let document = params.get("textDocument");
+                let text = document.get("text").as_str().unwrap_or_default();
+                self.open(document.get("uri"), text.to_string())?;
+            }
+            "textDocument/didChange" => {
+                let changes = params.get("contentChanges").as_array().unwrap_or_default();
+                if let Some(change) = changes.last() {
+                    let text = change.get("text").as_str().unwrap_or_default();
+                    self.open(params.get("textDocument").get("uri"), text.to_string())?;
+                }
+            }
+            "textDocument/didClose" => {
+                let uri = params.get("textDocument").get("uri");
+                if let Some(uri) = uri.as_str() {
+                    self.documents.remove(uri);
+                    self.publish(uri, Vec::new())?;
+                }
+            }
+            // Notably `initialized`, which needs no answer.
+            _ => (),
+        }
+
+        Ok(None)
+    }
+
+    fn request(&mut self, method: &str, params: &Json) -> Response {
+        if !self.initialized && method != "initialize" {
+            return Err(error(SERVER_NOT_INITIALIZED, "Server not initialized"));
+        }
+        if self.shutdown {
+            return Err(error(INVALID_REQUEST, "Server is shut down"));
+        }
+
+        match method {
+            "initialize" => {
+                self.initialized = true;
+                Ok(capabilities())
+            }
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            }
+            "textDocument/semanticTokens/full" => {
+                let data = self.document(params)?.semantic_tokens();
+                let data = data.into_iter().map(Json::from).collect::<Vec<_>>();
+                Ok(Json::object(vec![("data", Json::from(data))]))
+            }
+            "textDocument/definition" => self.definition(params),
+            "textDocument/hover" => self.hover(params),
+            "textDocument/documentSymbol" => self.symbols(params),
+            "textDocument/formatting" => self.format(params),
+            _ => Err(error(
+                METHOD_NOT_FOUND,
+                &format!("Unknown method {}", method),
+            )),
+        }
+    }
+
+    fn open(&mut self, uri: &Json, text: String) -> io::Result<()> {
+        let uri = match uri.as_str() {
+            Some(uri) => uri,
+            None => return Ok(()),
+        };
+
+        let document = Document::new(text);
+        let diagnostics = document.diagnostics();
+        self.documents.insert(uri.to_string(), document);
+
+        self.publish(uri, diagnostics)
+    }
+
+    fn document(&self, params: &Json) -> Result<&Document, ResponseError> {
+        params
+            .get("textDocument")
+            .get("uri")
+            .as_str()
+            .and_then(|uri| self.documents.get(uri))
+            .ok_or_else(|| error(INVALID_PARAMS, "Unknown document"))
+    }
+
+    /// The document of a request and the character offset of its position.
+    fn position(&self, params: &Json) -> Result<(&Document, usize), ResponseError> {
+        let document = self.document(params)?;
+        let position = params.get("position");
+        match (
+            position.get("line").as_usize(),
+            position.get("character").as_usize(),
+        ) {
+            (Some(line), Some(character)) => Ok((document, document.offset(line, character))),
+            _ => Err(error(INVALID_PARAMS, "Invalid position")),
+        }
+    }
+
+    fn definition(&self, params: &Json) -> Response {
+        let (document, offset) = self.position(params)?;
+
+        Ok(match document.analysis.declaration_at(offset) {
+            Some(declaration) => Json::object(vec![
+                ("uri", params.get("textDocument").get("uri").clone()),
+                ("range", document.range(declaration.name.span)),
+            ]),
+            None => Json::Null,
+        })
+    }
+
+    fn hover(&self, params: &Json) -> Response {
+        let (document, offset) = self.position(params)?;
+        let analysis = &document.analysis;
+
+        let reference = match analysis.reference_at(offset) {
+            Some(reference) => reference,
+            None => return Ok(Json::Null),
+        };
+        let value = match reference.declaration.map(|i| &analysis.declarations[i]) {
+            Some(declaration) => format!(
+                "```kentauri\n{}\n```\n{} variable declared on line {}",
+                document.text_of(declaration.statement),
+                if declaration.global {
+                    "Global"
+                } else {
+                    "Local"
+                },
+                declaration.name.line
+            ),
+            None => String::from("Undeclared global variable"),
+        };
+
+        Ok(Json::object(vec![
+            (
+                "contents",
+                Json::object(vec![
+                    ("kind", Json::from("markdown")),
+                    ("value", Json::from(value)),
+                ]),
+            ),
+            ("range", document.range(reference.span)),
+        ]))
+    }
+
+    fn symbols(&self, params: &Json) -> Response {
+        let document = self.document(params)?;
+
+        let symbols = document
+            .analysis
+            .declarations
+            .iter()
+            .map(|declaration| {
+                Json::object(vec![
+                    ("name", Json::from(declaration.name.name.as_str())),
+                    (
+                        "detail",
+                        Json::from(if declaration.global {
+                            "global"
+                        } else {
+                            "local"
+                        }),
+                    ),
+                    ("kind", Json::from(SYMBOL_VARIABLE)),
+                    ("range", document.range(declaration.statement)),
+                    ("selectionRange", document.range(declaration.name.span)),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Json::from(symbols))
+    }
+
+    /// Replaces the whole document with its formatted text, or does nothing when it does
+    /// not parse.
+    fn format(&self, params: &Json) -> Response {
+        let document = self.document(params)?;
+
+        Ok(match format(&document.text) {
+            Ok(formatted) if formatted == document.text => Json::from(Vec::new()),
+            Ok(formatted) => Json::from(vec![Json::object(vec![
+                ("range", document.whole_range()),
+                ("newText", Json::from(formatted)),
+            ])]),
+            Err(_) => Json::Null,
+        })
+    }
+
+    fn publish(&mut self, uri: &str, diagnostics: Vec<Json>) -> io::Result<()> {
+        let params = Json::object(vec![
+            ("uri", Json::from(uri)),
+            ("diagnostics", Json::from(diagnostics)),
+        ]);
+
+        write_message(
+            &mut self.writer,
+            &Json::object(vec![
+                ("jsonrpc", Json::from("2.0")),
+                ("method", Json::from("textDocument/publishDiagnostics")),
+                ("params", params),
+            ]),
+        )
+    }
+
+    fn respond(&mut self, id: &Json, response: Response) -> io::Result<()> {
+        let outcome = match response {
+            Ok(result) => ("result", result),
+            Err(e) => (
+                "error",
+                Json::object(vec![
+                    ("code", Json::Number(e.code as f64)),
+                    ("message", Json::from(e.message)),
+                ]),
+            ),
+        };
+
+        write_message(
+            &mut self.writer,
+            &Json::object(vec![
+                ("jsonrpc", Json::from("2.0")),
+                ("id", id.clone()),
+                outcome,
+            ]),
+        )
+    }
+}
+
+fn capabilities() -> Json {
+    let legend = Json::object(vec![
+        (
+            "tokenTypes",
+            Json::from(
+                TOKEN_TYPES
+                    .iter()
+                    .map(|t| Json::from(*t))
+                    .collect::<Vec<_>>(),
+            ),
+        ),
+        ("tokenModifiers", Json::from(Vec::new())),
+    ]);
+
+    Json::object(vec![
+        (
+            "capabilities",
+            Json::object(vec![
+                ("positionEncoding", Json::from("utf-16")),
+                ("textDocumentSync", Json::from(SYNC_FULL)),
+                ("definitionProvider", Json::from(true)),
+                ("hoverProvider", Json::from(true)),
+                ("documentSymbolProvider", Json::from(true)),
+                ("documentFormattingProvider", Json::from(true)),
+                (
+                    "semanticTokensProvider",
+                    Json::object(vec![("legend", legend), ("full", Json::from(true))]),
+                ),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::object(vec![
+                ("name", Json::from("kentauri")),
+                ("version", Json::from(env!("CARGO_PKG_VERSION"))),
+            ]),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Server;
+    use crate::util::json::Json;
+    use crate::util::rpc::read_message;
+    use std::io::Cursor;
+
+    fn request(id: usize, method: &str, params: Json) -> Json {
+        Json::object(vec![
+            ("jsonrpc", Json::from("2.0")),
+            ("id", Json::from(id)),
+            ("method", Json::from(method)),
+            ("params", params),
+        ])
+    }
+
+    fn responses(server: Server<Vec<u8>>) -> Vec<Json> {
+        let mut reader = Cursor::new(server.writer);
+        let mut responses = Vec::new();
+        while let Some(message) = read_message(&mut reader).unwrap() {
+            responses.push(message.unwrap());
+        }
+        responses
+    }
+
+    #[test]
+    fn test_lifecycle() {
+        let mut server = Server::new(Vec::new());
+
+        server.handle(&request(1, "shutdown", Json::Null)).unwrap();
+        server
+            .handle(&request(2, "initialize", Json::Null))
+            .unwrap();
+        server
+            .handle(&request(3, "textDocument/rename", Json::Null))
+            .unwrap();
+        server.handle(&request(4, "shutdown", Json::Null)).unwrap();
+        let exit = Json::object(vec![("method", Json::from("exit"))]);
+        assert_eq!(Some(0), server.handle(&exit).unwrap());
+
+        let responses = responses(server);
+        let error_code = |i: usize| responses[i].get("error").get("code").as_f64();
+        assert_eq!(Some(-32002.0), error_code(0));
+        assert!(responses[1]
+            .get("result")
+            .get("capabilities")
+            .get("hoverProvider")
+            .as_bool()
+            .unwrap());
+        assert_eq!(Some(-32601.0), error_code(2));
+        assert!(responses[3].get("result").is_null());
+        assert!(responses[3].get("error").is_null());
+    }
+}
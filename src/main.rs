@@ -1,21 +1,355 @@
-use kentauri::bytecode::chunk::Chunk;
-use kentauri::bytecode::opcode::OpCode;
-use kentauri::debug::disassembler::disassemble_chunk;
-use kentauri::interpreter::interpreter::Interpreter;
-use kentauri::vm::vm::VM;
+use kentauri::bytecode::binary::is_bytecode;
+use kentauri::compiler::lint::{Lint, LintConfig};
+use kentauri::compiler::peephole::OptLevel;
+use kentauri::dap;
+use kentauri::debug::console::Console;
+use kentauri::debug::coverage::{Coverage, Lcov};
+use kentauri::debug::profiler::Profiler;
+use kentauri::error::error::Error;
+use kentauri::error::interpreter::InterpreterError;
+use kentauri::interpreter::interpreter::{
+    read_input, write_output, Interpreter, InterpreterResult,
+};
+use kentauri::lsp;
+use kentauri::vm::config::VMConfig;
+use std::convert::TryFrom;
 use std::env;
+use std::io;
+use std::path::Path;
 use std::process::exit;
+use std::str::FromStr;
+
+const EX_USAGE: i32 = 64;
+const EX_IOERR: i32 = 74;
+// Returned by fmt --check when the script would change.
+const EX_UNFORMATTED: i32 = 1;
+// Where --profile writes collapsed stacks without -o.
+const PROFILE_OUTPUT: &str = "profile.folded";
+// Where --coverage merges its report without -o.
+const COVERAGE_OUTPUT: &str = "lcov.info";
+
+const USAGE: &str = "Usage: kentauri [command] [options] [<path> | - | -e <code>]
+
+Commands:
+  run       Run a script or compiled bytecode (default when a script is given)
+  repl      Start an interactive session (default without a script)
+  check     Compile a script and report errors and warnings without running it
+  disasm    Print the bytecode of a script
+  compile   Compile a script to bytecode, requires -o <output>
+  fmt       Format a script in place, or print it when read from stdin or -e
+  debug     Run a script under a debugger reading commands from stdin, help lists them
+  lsp       Start a language server on stdin and stdout
+  dap       Start a debug adapter on stdin and stdout
+
+Options:
+  -e <code>          Use <code> as the script
+  -o <output>        Output file of compile, of the stacks of --profile or of the report of
+                     --coverage
+  -A <lint>          Do not warn about <lint> in check: unused_variable, unused_assignment,
+                     shadowing or constant_comparison
+  -O <level>         Optimisation level: 0 none, 1 peephole, 2 superinstructions (default,
+                     except for debug)
+  --single-pass      Compile without building a syntax tree, which is faster
+  --trace            Print every instruction and the stack while running
+  --profile          Print where run spends its time to stderr, and write the time of each
+                     line as collapsed stacks for flame graphs to profile.folded
+  --coverage         Print which lines run ran, and add the runs of each line to the lcov
+                     report in lcov.info
+  --json             Print disasm output as JSON
+  --check            Make fmt report whether the script is formatted instead of formatting it
+  --max-stack <n>    Maximum depth of the value stack
+  --max-heap <n>     Maximum bytes of strings reachable at runtime
+  --fuel <n>         Maximum number of instructions to execute
+  -h, --help         Print this message
+
+A path of - reads the script from stdin.";
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Command {
+    Run,
+    Repl,
+    Check,
+    Disasm,
+    Compile,
+    Fmt,
+    Debug,
+    Lsp,
+    Dap,
+}
+
+enum Input {
+    Path(String),
+    Code(String),
+}
+
+struct Options {
+    command: Command,
+    input: Option<Input>,
+    output: Option<String>,
+    opt_level: Option<OptLevel>,
+    lints: LintConfig,
+    single_pass: bool,
+    trace: bool,
+    profile: bool,
+    coverage: bool,
+    json: bool,
+    check: bool,
+    config: VMConfig,
+    fuel: Option<u64>,
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let mut interpreter = Interpreter::new();
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let options = parse_args(&args).unwrap_or_else(|message| {
+        eprintln!("{}\n\n{}", message, USAGE);
+        exit(EX_USAGE);
+    });
+
+    if let Command::Lsp | Command::Dap = options.command {
+        let stdin = io::stdin();
+        let result = match options.command {
+            Command::Lsp => lsp::server::run(stdin.lock(), io::stdout()),
+            _ => dap::adapter::run(stdin.lock(), io::stdout()),
+        };
+        exit(result.unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            EX_IOERR
+        }));
+    }
+
+    let mut interpreter = Interpreter::with_config(options.config);
+    // Optimised code fuses instructions of different lines, which makes stepping jumpy.
+    let default_level = match options.command {
+        Command::Debug => OptLevel::None,
+        _ => OptLevel::default(),
+    };
+    interpreter.set_opt_level(options.opt_level.unwrap_or(default_level));
+    interpreter.set_single_pass(options.single_pass);
+    if options.command == Command::Check {
+        interpreter.set_lints(Some(options.lints.clone()));
+    }
+    interpreter.set_trace(options.trace);
+    interpreter.set_fuel(options.fuel);
+
+    if let Err(e) = execute(&mut interpreter, &options) {
+        interpreter.report(&e);
+        exit(e.exit_code());
+    }
+}
+
+fn execute(interpreter: &mut Interpreter, options: &Options) -> InterpreterResult<()> {
+    let (name, bytes) = match &options.input {
+        None => {
+            interpreter.repl();
+            return Ok(());
+        }
+        Some(Input::Path(path)) => (path.as_str(), read_input(path)?),
+        Some(Input::Code(code)) => ("-e", code.as_bytes().to_vec()),
+    };
+
+    match options.command {
+        Command::Run if options.profile => {
+            let chunk = interpreter.load(&bytes)?;
+            let mut profiler = Profiler::new(&chunk);
+            let result = interpreter.execute_with(chunk, &mut profiler);
+            profiler.finish();
 
-    if args.len() > 2 {
-        println!("Usage: <path>");
-        exit(64);
-    } else if args.len() == 2 {
-        interpreter.run_file(args.get(1).unwrap())
-    } else {
-        interpreter.repl();
+            eprint!("{}", profiler.report());
+            let path = options.output.as_deref().unwrap_or(PROFILE_OUTPUT);
+            let written = write_output(path, profiler.collapsed(name).as_bytes());
+            result.and(written)
+        }
+        Command::Run if options.coverage => {
+            let chunk = interpreter.load(&bytes)?;
+            let mut coverage = Coverage::new(&chunk);
+            let result = interpreter.execute_with(chunk, &mut coverage);
+
+            let path = options.output.as_deref().unwrap_or(COVERAGE_OUTPUT);
+            let merged = merge_coverage(path, name, &coverage);
+            result.and(merged)
+        }
+        Command::Run | Command::Repl => interpreter.run(&bytes),
+        Command::Check => interpreter.load(&bytes).map(|_| ()),
+        Command::Disasm => {
+            let disassembly = interpreter.disassemble(&bytes, name)?;
+            if options.json {
+                println!("{}", disassembly.to_json());
+            } else {
+                print!("{}", disassembly);
+            }
+            Ok(())
+        }
+        Command::Compile => {
+            let chunk = interpreter.load(&bytes)?;
+            interpreter.save_bytecode(&chunk, options.output.as_ref().unwrap())
+        }
+        Command::Debug => {
+            let chunk = interpreter.load(&bytes)?;
+            let stdin = io::stdin();
+            let mut console = Console::new(&chunk, stdin.lock());
+            if !is_bytecode(&bytes) {
+                console.set_source(&String::from_utf8_lossy(&bytes));
+            }
+
+            match interpreter.execute_with(chunk, &mut console) {
+                Err(InterpreterError::Interrupted) if console.has_quit() => Ok(()),
+                result => result,
+            }
+        }
+        Command::Lsp | Command::Dap => unreachable!(),
+        Command::Fmt => {
+            let formatted = interpreter.format(&bytes)?;
+            let unchanged = formatted.as_bytes() == bytes.as_slice();
+
+            match &options.input {
+                _ if options.check => {
+                    if !unchanged {
+                        eprintln!("{} is not formatted", name);
+                        exit(EX_UNFORMATTED);
+                    }
+                }
+                Some(Input::Path(path)) if path != "-" => {
+                    if !unchanged {
+                        write_output(path, formatted.as_bytes())?;
+                    }
+                }
+                _ => print!("{}", formatted),
+            }
+            Ok(())
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut args = args.iter().map(String::as_str).peekable();
+
+    let command = match args.peek() {
+        Some(&"run") => Some(Command::Run),
+        Some(&"repl") => Some(Command::Repl),
+        Some(&"check") => Some(Command::Check),
+        Some(&"disasm") => Some(Command::Disasm),
+        Some(&"compile") => Some(Command::Compile),
+        Some(&"fmt") => Some(Command::Fmt),
+        Some(&"debug") => Some(Command::Debug),
+        Some(&"lsp") => Some(Command::Lsp),
+        Some(&"dap") => Some(Command::Dap),
+        _ => None,
+    };
+    if command.is_some() {
+        args.next();
+    }
+
+    let mut options = Options {
+        command: command.unwrap_or(Command::Run),
+        input: None,
+        output: None,
+        opt_level: None,
+        lints: LintConfig::new(),
+        single_pass: false,
+        trace: false,
+        profile: false,
+        coverage: false,
+        json: false,
+        check: false,
+        config: VMConfig::default(),
+        fuel: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+
+        match arg {
+            "-e" => set_input(&mut options, Input::Code(value()?.to_string()))?,
+            "-o" => options.output = Some(value()?.to_string()),
+            "-A" => options.lints.allow(value()?.parse::<Lint>()?),
+            "-O" => {
+                let level: u8 = parse_number(arg, value()?)?;
+                options.opt_level = Some(
+                    OptLevel::try_from(level)
+                        .map_err(|_| format!("Invalid value for -O: {}", level))?,
+                );
+            }
+            "--single-pass" => options.single_pass = true,
+            "--trace" => options.trace = true,
+            "--profile" => options.profile = true,
+            "--coverage" => options.coverage = true,
+            "--json" => options.json = true,
+            "--check" => options.check = true,
+            "--max-stack" => options.config.max_stack = parse_number(arg, value()?)?,
+            "--max-heap" => options.config.max_heap = parse_number(arg, value()?)?,
+            "--fuel" => options.fuel = Some(parse_number(arg, value()?)?),
+            "-" => set_input(&mut options, Input::Path(arg.to_string()))?,
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+            _ => set_input(&mut options, Input::Path(arg.to_string()))?,
+        }
     }
+
+    match (options.command, &options.input) {
+        (command, _) if options.profile && command != Command::Run => {
+            Err("--profile only applies to run".to_string())
+        }
+        (command, _) if options.coverage && command != Command::Run => {
+            Err("--coverage only applies to run".to_string())
+        }
+        _ if options.profile && options.coverage => {
+            Err("--profile and --coverage cannot be combined".to_string())
+        }
+        (Command::Repl, Some(_)) => Err("repl does not take a script".to_string()),
+        (Command::Run, None) if command.is_none() && !options.profile && !options.coverage => {
+            options.command = Command::Repl;
+            Ok(options)
+        }
+        (Command::Repl, None) => Ok(options),
+        (Command::Lsp, Some(_)) => Err("lsp does not take a script".to_string()),
+        (Command::Lsp, None) => Ok(options),
+        (Command::Dap, Some(_)) => Err("dap does not take a script, clients launch it".to_string()),
+        (Command::Dap, None) => Ok(options),
+        (Command::Debug, Some(Input::Path(path))) if path == "-" => {
+            Err("debug reads commands from stdin, the script cannot be read from it".to_string())
+        }
+        (_, None) => Err("Missing script".to_string()),
+        (Command::Compile, Some(_)) if options.output.is_none() => {
+            Err("compile requires -o <output>".to_string())
+        }
+        _ => Ok(options),
+    }
+}
+
+/// Adds the runs of `coverage` to those of the script `name` in the lcov report at `path`,
+/// and prints a summary of the result.
+fn merge_coverage(path: &str, name: &str, coverage: &Coverage) -> InterpreterResult<()> {
+    let mut lcov = Lcov::new();
+    if Path::new(path).exists() {
+        let report = read_input(path)?;
+        lcov = Lcov::parse(&String::from_utf8_lossy(&report))
+            .map_err(|e| InterpreterError::IoError(Error::message(&format!("{}: {}", path, e))))?;
+    }
+    lcov.add(name, coverage);
+    write_output(path, lcov.to_string().as_bytes())?;
+
+    let (run, total) = coverage.instructions();
+    eprint!("{}", lcov.summary(name));
+    eprintln!("This run: {} of {} instructions", run, total);
+    Ok(())
+}
+
+fn set_input(options: &mut Options, input: Input) -> Result<(), String> {
+    if options.input.is_some() {
+        return Err("Only one script can be given".to_string());
+    }
+
+    options.input = Some(input);
+    Ok(())
+}
+
+fn parse_number<T: FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", option, value))
 }
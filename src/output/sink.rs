@@ -0,0 +1,73 @@
+use std::cell::RefCell;
+use std::io;
+use std::io::Write;
+use std::rc::Rc;
+
+/// Shared destination for script output or diagnostics. Clones write to the same target.
+#[derive(Clone)]
+pub struct Sink {
+    target: Rc<RefCell<Box<dyn Write>>>,
+}
+
+impl Sink {
+    pub fn new<W: Write + 'static>(writer: W) -> Self {
+        Sink {
+            target: Rc::new(RefCell::new(Box::new(writer))),
+        }
+    }
+
+    pub fn stdout() -> Self {
+        Sink::new(io::stdout())
+    }
+
+    pub fn stderr() -> Self {
+        Sink::new(io::stderr())
+    }
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.target.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.target.borrow_mut().flush()
+    }
+}
+
+/// In-memory sink that keeps everything written to it until it is taken.
+#[derive(Clone, Default)]
+pub struct OutputBuffer {
+    buffer: Rc<RefCell<Vec<u8>>>,
+}
+
+impl OutputBuffer {
+    pub fn new() -> Self {
+        OutputBuffer::default()
+    }
+
+    pub fn sink(&self) -> Sink {
+        Sink::new(self.clone())
+    }
+
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.borrow()).into_owned()
+    }
+
+    /// Returns the captured output and empties the buffer.
+    pub fn take(&self) -> String {
+        let bytes = self.buffer.replace(Vec::new());
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+impl Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
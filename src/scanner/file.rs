@@ -1,17 +1,23 @@
+use crate::scanner::token::Comment;
+use crate::syntax::span::Span;
+
 pub struct SourceController {
-    pub source: String,
+    pub source: Vec<char>,
     pub start: usize,
     pub current: usize,
     pub line: usize,
+    /// Comments skipped so far, only collected when set.
+    pub comments: Option<Vec<Comment>>,
 }
 
 impl SourceController {
     pub fn new(source: &str) -> Self {
         SourceController {
-            source: String::from(source),
+            source: source.chars().collect(),
             start: 0,
             current: 0,
             line: 1,
+            comments: None,
         }
     }
 
@@ -26,10 +32,6 @@ impl SourceController {
         self.query(self.current)
     }
 
-    pub fn query_previous(&self) -> char {
-        self.query(self.current - 1)
-    }
-
     pub fn query_next(&self) -> char {
         if self.current + 1 >= self.source.len() {
             return '\0';
@@ -49,20 +51,33 @@ impl SourceController {
                     self.line += 1;
                     self.advance();
                 }
-                '/' => {
-                    if self.query_next() == '/' {
-                        while self.query_current() != '\n' && !self.is_eof() {
-                            self.advance();
-                        }
+                '/' if self.query_next() == '/' => {
+                    let start = self.current;
+                    while self.query_current() != '\n' && !self.is_eof() {
+                        self.advance();
+                    }
+
+                    if let Some(comments) = &mut self.comments {
+                        let trailing = self.source[..start]
+                            .iter()
+                            .rev()
+                            .take_while(|c| **c != '\n')
+                            .any(|c| !c.is_whitespace());
+                        comments.push(Comment {
+                            text: self.source[start..self.current].iter().collect(),
+                            span: Span::new(start, self.current),
+                            line: self.line,
+                            trailing,
+                        });
                     }
                 }
-                _ => return (),
+                _ => return,
             };
         }
     }
 
     fn query(&self, n: usize) -> char {
-        self.source.chars().nth(n).unwrap()
+        self.source[n]
     }
 
     pub fn advance(&mut self) -> char {
@@ -89,18 +104,10 @@ impl SourceController {
     }
 
     pub fn extract_as_string(&self, start: usize, end: usize) -> String {
-        String::from(&self.source.as_str()[start..end])
+        self.source[start..end].iter().collect()
     }
 
     pub fn is_eof(&self) -> bool {
-        self.current as usize >= self.source.len()
-    }
-
-    pub fn is_closed(&self, enclosing: char) -> bool {
-        self.query_current() == enclosing || self.is_eof()
-    }
-
-    pub fn is_newline(&self) -> bool {
-        self.query_current() == '\n'
+        self.current >= self.source.len()
     }
 }
@@ -1,5 +1,6 @@
 use crate::scanner::file::SourceController;
-use crate::scanner::token::{Token, TokenType};
+use crate::scanner::token::{Comment, Token, TokenType};
+use crate::syntax::span::Span;
 
 pub struct Scanner {
     source: SourceController,
@@ -12,6 +13,19 @@ impl Scanner {
         }
     }
 
+    /// Collects the comments between tokens, to be taken with `take_comments`.
+    pub fn set_keep_comments(&mut self, keep: bool) {
+        self.source.comments = if keep { Some(Vec::new()) } else { None };
+    }
+
+    /// The comments skipped since the last call, in source order.
+    pub fn take_comments(&mut self) -> Vec<Comment> {
+        match self.source.comments.as_mut() {
+            Some(comments) => std::mem::take(comments),
+            None => Vec::new(),
+        }
+    }
+
     pub fn scan_token(&mut self) -> Token {
         self.source.skip_whitespaces();
 
@@ -23,7 +37,7 @@ impl Scanner {
 
         let char = self.source.advance();
 
-        return match char {
+        match char {
             '(' => self.make_token(TokenType::LEFT_PAREN),
             ')' => self.make_token(TokenType::RIGHT_PAREN),
             '{' => self.make_token(TokenType::LEFT_BRACE),
@@ -34,51 +48,60 @@ impl Scanner {
             '+' => self.make_token(TokenType::PLUS),
             ';' => self.make_token(TokenType::SEMICOLON),
             '*' => self.make_token(TokenType::STAR),
+            '/' => self.make_token(TokenType::SLASH),
             '!' => {
-                (if self.source.advance_match('=') {
+                if self.source.advance_match('=') {
                     self.make_token(TokenType::BANG_EQUAL)
                 } else {
                     self.make_token(TokenType::BANG)
-                })
+                }
             }
             '=' => {
-                (if self.source.advance_match('=') {
+                if self.source.advance_match('=') {
                     self.make_token(TokenType::EQUAL_EQUAL)
                 } else {
                     self.make_token(TokenType::EQUAL)
-                })
+                }
             }
             '<' => {
-                (if self.source.advance_match('=') {
+                if self.source.advance_match('=') {
                     self.make_token(TokenType::LESS_EQUAL)
                 } else {
                     self.make_token(TokenType::LESS)
-                })
+                }
             }
             '>' => {
-                (if self.source.advance_match('=') {
+                if self.source.advance_match('=') {
                     self.make_token(TokenType::GREATER_EQUAL)
                 } else {
                     self.make_token(TokenType::GREATER)
-                })
+                }
             }
             '"' => self.string(),
-            '0'...'9' => self.number(),
-            'a'...'z' | 'A'...'Z' | '_' => self.identifier(),
+            '0'..='9' => self.number(),
+            'a'..='z' | 'A'..='Z' | '_' => self.identifier(),
             _ => self.make_error("Unexpected character."),
-        };
+        }
     }
 
     fn make_token(&mut self, token_type: TokenType) -> Token {
-        Token::new(
+        let mut token = Token::new(
             token_type,
             self.source.extract_from_start(),
             self.source.line,
-        )
+        );
+        token.span = self.span();
+        token
     }
 
     fn make_error(&mut self, message: &str) -> Token {
-        Token::error(message, self.source.line)
+        let mut token = Token::error(message, self.source.line);
+        token.span = self.span();
+        token
+    }
+
+    fn span(&self) -> Span {
+        Span::new(self.source.start, self.source.current)
     }
 
     fn string(&mut self) -> Token {
@@ -166,6 +189,7 @@ fn is_a_keyword(a: &str, b: &str, token_type: TokenType) -> TokenType {
 mod tests {
     use super::Scanner;
     use crate::scanner::token::TokenType;
+    use crate::syntax::span::Span;
 
     #[test]
     fn test_identifier_trie() {
@@ -176,4 +200,37 @@ mod tests {
         assert_eq!(TokenType::THIS, reserved.token_type);
         assert_eq!(TokenType::IDENTIFIER, non_reserved.token_type);
     }
+
+    #[test]
+    fn test_keep_comments() {
+        let mut scanner = Scanner::new("// one\nprint 1; // two\n");
+        scanner.set_keep_comments(true);
+        while scanner.scan_token().token_type != TokenType::EOF {}
+
+        let comments = scanner.take_comments();
+        assert_eq!(2, comments.len());
+        assert_eq!("// one", comments[0].text);
+        assert_eq!(1, comments[0].line);
+        assert_eq!("// two", comments[1].text);
+        assert_eq!(Span::new(16, 22), comments[1].span);
+        assert_eq!(2, comments[1].line);
+        assert!(!comments[0].trailing);
+        assert!(comments[1].trailing);
+    }
+
+    #[test]
+    fn test_slash_and_comment() {
+        let mut scanner = Scanner::new("8 / 2 // half");
+        let types: Vec<TokenType> = (0..4).map(|_| scanner.scan_token().token_type).collect();
+
+        assert_eq!(
+            vec![
+                TokenType::NUMBER,
+                TokenType::SLASH,
+                TokenType::NUMBER,
+                TokenType::EOF
+            ],
+            types
+        );
+    }
 }
@@ -1,3 +1,4 @@
+use crate::syntax::span::Span;
 use std::fmt::{Display, Error, Formatter};
 
 #[derive(Debug, Clone, PartialEq, Hash, Eq)]
@@ -5,6 +6,7 @@ pub struct Token {
     pub token_type: TokenType,
     pub lexem: String,
     pub line: usize,
+    pub span: Span,
 }
 
 impl Token {
@@ -13,6 +15,7 @@ impl Token {
             token_type,
             lexem,
             line,
+            span: Span::default(),
         }
     }
 
@@ -21,6 +24,7 @@ impl Token {
             token_type: TokenType::EOF,
             lexem: String::from(""),
             line,
+            span: Span::default(),
         }
     }
 
@@ -28,11 +32,22 @@ impl Token {
         Token {
             token_type: TokenType::ERROR,
             lexem: String::from(message),
-            line: 0,
+            line,
+            span: Span::default(),
         }
     }
 }
 
+/// A `//` comment, kept by the scanner on request. The text includes the slashes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub text: String,
+    pub span: Span,
+    pub line: usize,
+    /// Whether the comment follows code on its line.
+    pub trailing: bool,
+}
+
 impl Display for Token {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         write!(f, "{}: {} <{:?}>", self.line, self.lexem, self.token_type)
@@ -41,6 +56,7 @@ impl Display for Token {
 
 #[derive(Debug, Copy, Clone, PartialEq, Hash, Eq)]
 #[repr(u8)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
 pub enum TokenType {
     LEFT_PAREN = 0,
     RIGHT_PAREN,
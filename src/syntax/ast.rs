@@ -0,0 +1,153 @@
+//! Syntax tree of a kentauri program. Every node keeps its span and the line of its last
+//! token, which is the line the code generated for it is attributed to.
+
+use crate::scanner::token::{Comment, TokenType};
+use crate::syntax::span::Span;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub statements: Vec<Stmt>,
+    /// Line of the end of input.
+    pub line: usize,
+    /// The comments of the source, when the parser was asked to keep them.
+    pub comments: Vec<Comment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StmtKind {
+    Var {
+        name: Identifier,
+        initializer: Option<Expr>,
+    },
+    Print(Expr),
+    Expression(Expr),
+    /// A trailing expression without `;`, only parsed in echo mode.
+    Echo(Expr),
+    Block(Vec<Stmt>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Number(f64),
+    String(String),
+    Bool(bool),
+    Nil,
+    Variable(Identifier),
+    Assign {
+        name: Identifier,
+        value: Box<Expr>,
+    },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+    },
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Grouping(Box<Expr>),
+    /// Stands in for an expression that failed to parse.
+    Invalid,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identifier {
+    pub name: String,
+    pub span: Span,
+    pub line: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum UnaryOp {
+    Negate,
+    Not,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+}
+
+impl UnaryOp {
+    pub fn from_token(token_type: TokenType) -> Option<Self> {
+        match token_type {
+            TokenType::MINUS => Some(UnaryOp::Negate),
+            TokenType::BANG => Some(UnaryOp::Not),
+            _ => None,
+        }
+    }
+}
+
+impl BinaryOp {
+    pub fn from_token(token_type: TokenType) -> Option<Self> {
+        let op = match token_type {
+            TokenType::PLUS => BinaryOp::Add,
+            TokenType::MINUS => BinaryOp::Subtract,
+            TokenType::STAR => BinaryOp::Multiply,
+            TokenType::SLASH => BinaryOp::Divide,
+            TokenType::EQUAL_EQUAL => BinaryOp::Equal,
+            TokenType::BANG_EQUAL => BinaryOp::NotEqual,
+            TokenType::GREATER => BinaryOp::Greater,
+            TokenType::GREATER_EQUAL => BinaryOp::GreaterEqual,
+            TokenType::LESS => BinaryOp::Less,
+            TokenType::LESS_EQUAL => BinaryOp::LessEqual,
+            _ => return None,
+        };
+
+        Some(op)
+    }
+}
+
+impl Display for UnaryOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            UnaryOp::Negate => write!(f, "-"),
+            UnaryOp::Not => write!(f, "!"),
+        }
+    }
+}
+
+impl Display for BinaryOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Subtract => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+            BinaryOp::Equal => "==",
+            BinaryOp::NotEqual => "!=",
+            BinaryOp::Greater => ">",
+            BinaryOp::GreaterEqual => ">=",
+            BinaryOp::Less => "<",
+            BinaryOp::LessEqual => "<=",
+        };
+
+        write!(f, "{}", symbol)
+    }
+}
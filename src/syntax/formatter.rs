@@ -0,0 +1,267 @@
+use crate::error::error::Error;
+use crate::scanner::token::Comment;
+use crate::syntax::ast::{Expr, ExprKind, Program, Stmt, StmtKind};
+use crate::syntax::parser::Parser;
+use crate::syntax::span::Span;
+
+const INDENT: &str = "    ";
+
+/// Formats `source` in the canonical style: one statement per line, blocks indented by four
+/// spaces, single spaces around binary operators and `=`, and at most one blank line where
+/// the source had any. Comments stay where they were relative to the statements, except
+/// those inside a statement, which move to the line before it. Long lines are not wrapped.
+///
+/// Fails with the syntax errors of `source`, which is left for the compiler to report.
+pub fn format(source: &str) -> Result<String, Vec<Error>> {
+    let mut parser = Parser::new(source);
+    parser.set_keep_comments(true);
+
+    let (program, errors) = parser.parse();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut formatter = Formatter::new(source, &program);
+    formatter.statements(&program.statements, usize::MAX);
+
+    Ok(formatter.out)
+}
+
+struct Formatter<'a> {
+    source: Vec<char>,
+    // Offset of the first character of each line.
+    line_starts: Vec<usize>,
+    comments: &'a [Comment],
+    next_comment: usize,
+    indent: usize,
+    out: String,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(source: &str, program: &'a Program) -> Self {
+        let source: Vec<char> = source.chars().collect();
+        let mut line_starts = vec![0];
+        for (offset, c) in source.iter().enumerate() {
+            if *c == '\n' {
+                line_starts.push(offset + 1);
+            }
+        }
+
+        Formatter {
+            source,
+            line_starts,
+            comments: &program.comments,
+            next_comment: 0,
+            indent: 0,
+            out: String::new(),
+        }
+    }
+
+    /// Writes `statements` and the comments among them, up to the offset `end`.
+    fn statements(&mut self, statements: &[Stmt], end: usize) {
+        // Source line of the end of the last thing written at this level.
+        let mut last_line = None;
+
+        for (i, statement) in statements.iter().enumerate() {
+            let next = statements.get(i + 1).map_or(end, |next| next.span.start);
+
+            // The comments inside a block stay inside, those inside any other statement
+            // move before it.
+            let before = match statement.kind {
+                StmtKind::Block(_) => statement.span.start,
+                _ => statement.span.end,
+            };
+            self.comments_before(before, &mut last_line);
+
+            self.separate(last_line, self.line_of(statement.span.start));
+            self.write_indent();
+            self.statement(statement);
+
+            if let Some(comment) = self.peek_comment() {
+                if comment.line == statement.line && comment.span.start < next {
+                    self.out.push(' ');
+                    self.out.push_str(comment.text.trim_end());
+                    self.next_comment += 1;
+                }
+            }
+            self.out.push('\n');
+            last_line = Some(statement.line);
+        }
+
+        self.comments_before(end, &mut last_line);
+    }
+
+    fn comments_before(&mut self, offset: usize, last_line: &mut Option<usize>) {
+        while let Some(comment) = self.peek_comment() {
+            if comment.span.start >= offset {
+                return;
+            }
+
+            self.separate(*last_line, comment.line);
+            self.write_indent();
+            self.out.push_str(comment.text.trim_end());
+            self.out.push('\n');
+
+            *last_line = Some(comment.line);
+            self.next_comment += 1;
+        }
+    }
+
+    fn peek_comment(&self) -> Option<&'a Comment> {
+        self.comments.get(self.next_comment)
+    }
+
+    /// Keeps a single blank line between things that were apart in the source.
+    fn separate(&mut self, last_line: Option<usize>, line: usize) {
+        if let Some(last_line) = last_line {
+            if line > last_line + 1 {
+                self.out.push('\n');
+            }
+        }
+    }
+
+    fn write_indent(&mut self) {
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    fn line_of(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(i) => i + 1,
+            Err(i) => i,
+        }
+    }
+
+    fn text(&self, span: Span) -> String {
+        self.source[span.start..span.end].iter().collect()
+    }
+}
+
+impl<'a> Formatter<'a> {
+    fn statement(&mut self, statement: &Stmt) {
+        match &statement.kind {
+            StmtKind::Var { name, initializer } => {
+                self.out.push_str("var ");
+                self.out.push_str(&name.name);
+                if let Some(initializer) = initializer {
+                    self.out.push_str(" = ");
+                    self.expression(initializer);
+                }
+                self.out.push(';');
+            }
+            StmtKind::Print(expr) => {
+                self.out.push_str("print ");
+                self.expression(expr);
+                self.out.push(';');
+            }
+            StmtKind::Expression(expr) => {
+                self.expression(expr);
+                self.out.push(';');
+            }
+            StmtKind::Echo(expr) => self.expression(expr),
+            StmtKind::Block(statements) => {
+                // The closing brace is the last character of the block.
+                let end = statement.span.end - 1;
+                let empty = statements.is_empty()
+                    && self.peek_comment().is_none_or(|c| c.span.start >= end);
+                if empty {
+                    self.out.push_str("{}");
+                    return;
+                }
+
+                self.out.push_str("{\n");
+                self.indent += 1;
+                self.statements(statements, end);
+                self.indent -= 1;
+                self.write_indent();
+                self.out.push('}');
+            }
+        }
+    }
+
+    fn expression(&mut self, expr: &Expr) {
+        match &expr.kind {
+            // Literals keep their spelling, `1.50` stays as it is.
+            ExprKind::Number(_) | ExprKind::String(_) => {
+                let text = self.text(expr.span);
+                self.out.push_str(&text);
+            }
+            ExprKind::Bool(true) => self.out.push_str("true"),
+            ExprKind::Bool(false) => self.out.push_str("false"),
+            ExprKind::Nil => self.out.push_str("nil"),
+            ExprKind::Variable(name) => self.out.push_str(&name.name),
+            ExprKind::Assign { name, value } => {
+                self.out.push_str(&name.name);
+                self.out.push_str(" = ");
+                self.expression(value);
+            }
+            ExprKind::Unary { op, operand } => {
+                self.out.push_str(&op.to_string());
+                self.expression(operand);
+            }
+            ExprKind::Binary { op, left, right } => {
+                self.expression(left);
+                self.out.push_str(&format!(" {} ", op));
+                self.expression(right);
+            }
+            ExprKind::Grouping(expr) => {
+                self.out.push('(');
+                self.expression(expr);
+                self.out.push(')');
+            }
+            ExprKind::Invalid => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format;
+
+    #[test]
+    fn test_format() {
+        let source = "var a=1+2*(3 -4);// sum\n\n\n{print a;var b=-a;{}\n  // inner\n}\nprint\n  \"x\"  ==\"y\"; a=!true;";
+        let expected = "var a = 1 + 2 * (3 - 4); // sum
+
+{
+    print a;
+    var b = -a;
+    {}
+    // inner
+}
+print \"x\" == \"y\";
+a = !true;
+";
+
+        assert_eq!(expected, format(source).unwrap());
+        assert_eq!(expected, format(expected).unwrap());
+    }
+
+    #[test]
+    fn test_format_comments() {
+        let source = "// head\nvar a = 1 + // one\n  2;\n{ // open\n} // close\n\n// tail\n";
+        let expected = "// head
+// one
+var a = 1 + 2;
+{
+    // open
+} // close
+
+// tail
+";
+
+        assert_eq!(expected, format(source).unwrap());
+        assert_eq!(expected, format(expected).unwrap());
+        assert_eq!("", format("").unwrap());
+    }
+
+    #[test]
+    fn test_format_errors() {
+        let errors = format("print ;").unwrap_err();
+        assert_eq!(
+            "[line 1] Error at ';': Expect expression",
+            errors[0].to_string()
+        );
+    }
+}
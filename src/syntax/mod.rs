@@ -0,0 +1,4 @@
+pub mod ast;
+pub mod formatter;
+pub mod parser;
+pub mod span;
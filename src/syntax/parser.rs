@@ -0,0 +1,495 @@
+use crate::compiler::precedence::{get_rule, ParseFn, Precedence};
+use crate::error::error::Error;
+use crate::scanner::scanner::Scanner;
+use crate::scanner::token::{Token, TokenType};
+use crate::syntax::ast::{BinaryOp, Expr, ExprKind, Identifier, Program, Stmt, StmtKind, UnaryOp};
+use crate::syntax::span::Span;
+
+// Deeper nesting of blocks and expressions would overflow the native stack of the parser.
+const MAX_NESTING: usize = 256;
+
+/// Parses source into a syntax tree. It accepts exactly what the single-pass compiler
+/// accepts and reports the same syntax errors; errors about names, such as redeclared
+/// locals, are left to the code generator.
+pub struct Parser {
+    scanner: Scanner,
+    current: Token,
+    previous: Token,
+    errors: Vec<Error>,
+    panic: bool,
+    // Set when the rest of the input was skipped, later errors would only be consequences.
+    aborted: bool,
+    nesting: usize,
+    // Depth of the block being parsed, zero at the top level.
+    depth: usize,
+    echo: bool,
+}
+
+impl Parser {
+    pub fn new(source: &str) -> Self {
+        Parser {
+            scanner: Scanner::new(source),
+            current: Token::eof(0),
+            previous: Token::eof(0),
+            errors: Vec::new(),
+            panic: false,
+            aborted: false,
+            nesting: 0,
+            depth: 0,
+            echo: false,
+        }
+    }
+
+    /// When enabled, a trailing expression without `;` is parsed as an echo statement
+    /// instead of being a syntax error, as the REPL wants.
+    pub fn set_echo(&mut self, echo: bool) {
+        self.echo = echo;
+    }
+
+    /// Keeps the comments of the source in the parsed program.
+    pub fn set_keep_comments(&mut self, keep: bool) {
+        self.scanner.set_keep_comments(keep);
+    }
+
+    /// Parses the whole source. On errors the program holds what could be parsed, with
+    /// `ExprKind::Invalid` where an expression was missing.
+    pub fn parse(mut self) -> (Program, Vec<Error>) {
+        let mut statements = Vec::new();
+
+        self.advance();
+        while !self.match_advance(TokenType::EOF) {
+            if let Some(statement) = self.declaration() {
+                statements.push(statement);
+            }
+        }
+
+        let program = Program {
+            statements,
+            line: self.previous.line,
+            comments: self.scanner.take_comments(),
+        };
+
+        (program, self.errors)
+    }
+
+    fn advance(&mut self) {
+        self.previous = std::mem::replace(&mut self.current, self.scanner.scan_token());
+
+        while self.current.token_type == TokenType::ERROR {
+            self.error_at_current("");
+            self.current = self.scanner.scan_token();
+        }
+    }
+
+    fn error_at_current(&mut self, message: &str) {
+        let token = self.current.clone();
+        self.error_at(token, message)
+    }
+
+    fn error_at_previous(&mut self, message: &str) {
+        let token = self.previous.clone();
+        self.error_at(token, message)
+    }
+
+    fn error_at(&mut self, token: Token, message: &str) {
+        if self.panic || self.aborted {
+            return;
+        }
+
+        self.panic = true;
+        self.errors.push(Error::new(token, message));
+    }
+
+    fn consume_if_expected(&mut self, token_type: TokenType, message: &str) {
+        if self.check(token_type) {
+            self.advance();
+        } else {
+            self.error_at_current(message);
+        }
+    }
+
+    fn match_advance(&mut self, token_type: TokenType) -> bool {
+        if self.check(token_type) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn check(&self, token_type: TokenType) -> bool {
+        self.current.token_type == token_type
+    }
+
+    fn synchronize(&mut self) {
+        self.panic = false;
+
+        while !self.check(TokenType::EOF) {
+            if self.previous.token_type == TokenType::SEMICOLON {
+                return;
+            }
+
+            match self.current.token_type {
+                TokenType::CLASS
+                | TokenType::FUN
+                | TokenType::VAR
+                | TokenType::FOR
+                | TokenType::IF
+                | TokenType::WHILE
+                | TokenType::PRINT
+                | TokenType::RETURN => return,
+                _ => self.advance(),
+            }
+        }
+    }
+
+    /// Counts one more level of nesting. Past the limit it reports an error and skips the
+    /// rest of the input, so the callers unwind without recursing any further.
+    fn enter_nesting(&mut self) -> bool {
+        self.nesting += 1;
+        if self.nesting <= MAX_NESTING {
+            return true;
+        }
+
+        self.error_at_current("Too much nesting");
+        self.aborted = true;
+        while !self.check(TokenType::EOF) {
+            self.advance();
+        }
+
+        false
+    }
+
+    /// An expression from the token at `start` to the previous one.
+    fn expr(&self, kind: ExprKind, start: Span) -> Expr {
+        Expr {
+            kind,
+            span: start.to(self.previous.span),
+            line: self.previous.line,
+        }
+    }
+
+    /// A statement from the token at `start` to the previous one.
+    fn stmt(&self, kind: StmtKind, start: Span) -> Stmt {
+        Stmt {
+            kind,
+            span: start.to(self.previous.span),
+            line: self.previous.line,
+        }
+    }
+
+    fn identifier(&self) -> Identifier {
+        Identifier {
+            name: self.previous.lexem.clone(),
+            span: self.previous.span,
+            line: self.previous.line,
+        }
+    }
+}
+
+impl Parser {
+    fn parse_precedence(&mut self, level: Precedence) -> Expr {
+        let expr = if self.enter_nesting() {
+            self.parse_nested_precedence(level)
+        } else {
+            self.expr(ExprKind::Invalid, self.previous.span)
+        };
+        self.nesting -= 1;
+
+        expr
+    }
+
+    fn parse_nested_precedence(&mut self, level: Precedence) -> Expr {
+        self.advance();
+
+        let prefix_rule = get_rule(&self.previous.token_type);
+        let prefix = match prefix_rule.prefix.as_ref() {
+            Some(prefix) => prefix,
+            None => {
+                self.error_at_previous("Expect expression");
+                return self.expr(ExprKind::Invalid, self.previous.span);
+            }
+        };
+
+        let is_assignable = level as u8 <= Precedence::ASSIGNMENT as u8;
+
+        let mut expr = self.prefix(prefix, is_assignable);
+
+        while level as u8 <= get_rule(&self.current.token_type).precedence as u8 {
+            self.advance();
+            expr = self.binary(expr);
+        }
+
+        if is_assignable && self.match_advance(TokenType::EQUAL) {
+            self.error_at_previous("Invalid assignment target.");
+        }
+
+        expr
+    }
+
+    fn expression(&mut self) -> Expr {
+        self.parse_precedence(Precedence::ASSIGNMENT)
+    }
+
+    fn prefix(&mut self, parse_fn: &ParseFn, is_assignable: bool) -> Expr {
+        let start = self.previous.span;
+
+        let kind = match *parse_fn {
+            ParseFn::Grouping => self.grouping(),
+            ParseFn::Number => self.number(),
+            ParseFn::Unary => self.unary(),
+            ParseFn::Literal => self.literal(),
+            ParseFn::String => self.string(),
+            ParseFn::Variable => self.variable(is_assignable),
+            ParseFn::Binary => ExprKind::Invalid,
+        };
+
+        self.expr(kind, start)
+    }
+
+    fn number(&mut self) -> ExprKind {
+        match self.previous.lexem.parse::<f64>() {
+            Ok(number) => ExprKind::Number(number),
+            Err(_) => {
+                self.error_at_previous("Invalid number");
+                ExprKind::Invalid
+            }
+        }
+    }
+
+    fn grouping(&mut self) -> ExprKind {
+        let expr = self.expression();
+
+        self.consume_if_expected(TokenType::RIGHT_PAREN, "Expect ')' after expression");
+        ExprKind::Grouping(Box::new(expr))
+    }
+
+    fn unary(&mut self) -> ExprKind {
+        let op = UnaryOp::from_token(self.previous.token_type);
+        let operand = self.parse_precedence(Precedence::UNARY);
+
+        match op {
+            Some(op) => ExprKind::Unary {
+                op,
+                operand: Box::new(operand),
+            },
+            None => ExprKind::Invalid,
+        }
+    }
+
+    fn binary(&mut self, left: Expr) -> Expr {
+        let token_type = self.previous.token_type;
+        let start = left.span;
+
+        let next_prec = match get_rule(&token_type).get_incremented_prec(1u8) {
+            Some(next_prec) => next_prec,
+            None => {
+                self.error_at_previous("Invalid precedence");
+                return self.expr(ExprKind::Invalid, start);
+            }
+        };
+        let right = self.parse_precedence(next_prec);
+
+        let kind = match BinaryOp::from_token(token_type) {
+            Some(op) => ExprKind::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            },
+            None => ExprKind::Invalid,
+        };
+
+        self.expr(kind, start)
+    }
+
+    fn literal(&mut self) -> ExprKind {
+        match self.previous.token_type {
+            TokenType::FALSE => ExprKind::Bool(false),
+            TokenType::NIL => ExprKind::Nil,
+            TokenType::TRUE => ExprKind::Bool(true),
+            _ => ExprKind::Invalid,
+        }
+    }
+
+    fn string(&mut self) -> ExprKind {
+        let lexem = &self.previous.lexem;
+        ExprKind::String(String::from(&lexem[1..lexem.len() - 1]))
+    }
+
+    fn variable(&mut self, is_assignable: bool) -> ExprKind {
+        let name = self.identifier();
+
+        if is_assignable && self.match_advance(TokenType::EQUAL) {
+            let value = self.expression();
+            ExprKind::Assign {
+                name,
+                value: Box::new(value),
+            }
+        } else {
+            ExprKind::Variable(name)
+        }
+    }
+}
+
+impl Parser {
+    fn declaration(&mut self) -> Option<Stmt> {
+        if !self.enter_nesting() {
+            self.nesting -= 1;
+            return None;
+        }
+
+        let statement = if self.match_advance(TokenType::VAR) {
+            self.var_declaration()
+        } else {
+            self.statement()
+        };
+        self.nesting -= 1;
+
+        if self.panic {
+            self.synchronize();
+        }
+
+        Some(statement)
+    }
+
+    fn statement(&mut self) -> Stmt {
+        if self.match_advance(TokenType::PRINT) {
+            self.print_statement()
+        } else if self.match_advance(TokenType::LEFT_BRACE) {
+            self.depth += 1;
+            let block = self.block();
+            self.depth -= 1;
+            block
+        } else {
+            self.expression_statement()
+        }
+    }
+
+    fn block(&mut self) -> Stmt {
+        let start = self.previous.span;
+        let mut statements = Vec::new();
+
+        while !self.check(TokenType::RIGHT_BRACE) && !self.check(TokenType::EOF) {
+            if let Some(statement) = self.declaration() {
+                statements.push(statement);
+            }
+        }
+
+        self.consume_if_expected(TokenType::RIGHT_BRACE, "Expect '}' after block.");
+        self.stmt(StmtKind::Block(statements), start)
+    }
+
+    fn print_statement(&mut self) -> Stmt {
+        let start = self.previous.span;
+        let expr = self.expression();
+
+        self.consume_if_expected(TokenType::SEMICOLON, "Expect ';' after value.");
+        self.stmt(StmtKind::Print(expr), start)
+    }
+
+    fn expression_statement(&mut self) -> Stmt {
+        let start = self.current.span;
+        let expr = self.expression();
+
+        if self.echo && self.depth == 0 && self.check(TokenType::EOF) {
+            return self.stmt(StmtKind::Echo(expr), start);
+        }
+
+        self.consume_if_expected(TokenType::SEMICOLON, "Expect ';' after expression.");
+        self.stmt(StmtKind::Expression(expr), start)
+    }
+
+    fn var_declaration(&mut self) -> Stmt {
+        let start = self.previous.span;
+
+        self.consume_if_expected(TokenType::IDENTIFIER, "Expect variable name.");
+        let name = self.identifier();
+
+        let initializer = if self.match_advance(TokenType::EQUAL) {
+            Some(self.expression())
+        } else {
+            None
+        };
+
+        self.consume_if_expected(
+            TokenType::SEMICOLON,
+            "Expect ';' after variable declaration",
+        );
+        self.stmt(StmtKind::Var { name, initializer }, start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Parser;
+    use crate::syntax::ast::{BinaryOp, ExprKind, StmtKind};
+    use crate::syntax::span::Span;
+
+    #[test]
+    fn test_parse() {
+        let (program, errors) = Parser::new("var a = 1 +\n  2 * 3;\n{ print -a; }").parse();
+        assert!(errors.is_empty());
+        assert_eq!(2, program.statements.len());
+        assert_eq!(3, program.line);
+
+        let var = &program.statements[0];
+        assert_eq!(Span::new(0, 20), var.span);
+        assert_eq!(2, var.line);
+        match &var.kind {
+            StmtKind::Var {
+                name,
+                initializer: Some(initializer),
+            } => {
+                assert_eq!("a", name.name);
+                assert_eq!(Span::new(4, 5), name.span);
+                assert_eq!(Span::new(8, 19), initializer.span);
+                match &initializer.kind {
+                    ExprKind::Binary { op, right, .. } => {
+                        assert_eq!(BinaryOp::Add, *op);
+                        assert!(matches!(
+                            right.kind,
+                            ExprKind::Binary {
+                                op: BinaryOp::Multiply,
+                                ..
+                            }
+                        ));
+                    }
+                    kind => panic!("unexpected initializer {:?}", kind),
+                }
+            }
+            kind => panic!("unexpected statement {:?}", kind),
+        }
+
+        assert!(matches!(&program.statements[1].kind, StmtKind::Block(b) if b.len() == 1));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let (program, errors) = Parser::new("print ;\nvar = 1;\nprint 2;\na + b = 3;").parse();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            vec![
+                "[line 1] Error at ';': Expect expression",
+                "[line 2] Error at '=': Expect variable name.",
+                "[line 4] Error at '=': Invalid assignment target.",
+            ],
+            messages
+        );
+        assert!(
+            matches!(&program.statements[0].kind, StmtKind::Print(e) if e.kind == ExprKind::Invalid)
+        );
+        assert_eq!(4, program.statements.len());
+    }
+
+    #[test]
+    fn test_parse_echo() {
+        let mut parser = Parser::new("1 + 2");
+        parser.set_echo(true);
+        let (program, errors) = parser.parse();
+        assert!(errors.is_empty());
+        assert!(matches!(program.statements[0].kind, StmtKind::Echo(_)));
+
+        let (_, errors) = Parser::new("1 + 2").parse();
+        assert_eq!(1, errors.len());
+    }
+}
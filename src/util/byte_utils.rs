@@ -1,3 +1,10 @@
 pub fn byte_array_to_u32(byte: &[u8; 4]) -> u32 {
-    ((byte[0] as u32) << 0) + ((byte[1] as u32) << 8) + ((byte[2] as u32) << 16)
+    u32::from_le_bytes(*byte)
+}
+
+/// 32-bit FNV-1a hash, used as the integrity checksum of serialized chunks.
+pub fn fnv1a_32(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash: u32, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x0100_0193)
+    })
 }
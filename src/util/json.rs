@@ -0,0 +1,384 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Quotes and escapes `s` as a JSON string.
+pub fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+/// A JSON value, as read and written by the language server and the debug adapter.
+/// Objects keep their keys in insertion order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// An object with the given members.
+    pub fn object(members: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// The member `key` of an object, `Json::Null` if there is none.
+    pub fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(k, _)| k == key)
+                .map_or(&Json::Null, |(_, value)| value),
+            _ => &Json::Null,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_f64()
+            .filter(|n| *n >= 0.0 && n.fract() == 0.0)
+            .map(|n| n as usize)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == Json::Null
+    }
+
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = JsonParser {
+            chars: text.chars().collect(),
+            at: 0,
+        };
+
+        let value = parser.value(0)?;
+        parser.skip_whitespace();
+        if parser.at < parser.chars.len() {
+            return Err(format!("Unexpected character at {}", parser.at));
+        }
+
+        Ok(value)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Json::String(s)
+    }
+}
+
+impl From<f64> for Json {
+    fn from(n: f64) -> Self {
+        Json::Number(n)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        Json::Bool(b)
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(values: Vec<Json>) -> Self {
+        Json::Array(values)
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) if n.is_finite() => write!(f, "{}", n),
+            Json::Number(_) => write!(f, "null"),
+            Json::String(s) => write!(f, "{}", quote(s)),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{}", quote(key), value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+// Deeper nesting would overflow the native stack of the parser.
+const MAX_DEPTH: usize = 128;
+
+struct JsonParser {
+    chars: Vec<char>,
+    at: usize,
+}
+
+impl JsonParser {
+    fn value(&mut self, depth: usize) -> Result<Json, String> {
+        if depth > MAX_DEPTH {
+            return Err(String::from("Too much nesting"));
+        }
+
+        self.skip_whitespace();
+        match self.peek() {
+            Some('n') => self.keyword("null", Json::Null),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('"') => self.string().map(Json::String),
+            Some('[') => {
+                self.at += 1;
+                let mut values = Vec::new();
+                if !self.next_is(']') {
+                    loop {
+                        values.push(self.value(depth + 1)?);
+                        if !self.next_is(',') {
+                            break;
+                        }
+                    }
+                    self.expect(']')?;
+                }
+                Ok(Json::Array(values))
+            }
+            Some('{') => {
+                self.at += 1;
+                let mut members = Vec::new();
+                if !self.next_is('}') {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.expect(':')?;
+                        members.push((key, self.value(depth + 1)?));
+                        if !self.next_is(',') {
+                            break;
+                        }
+                    }
+                    self.expect('}')?;
+                }
+                Ok(Json::Object(members))
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            _ => Err(format!("Expected a value at {}", self.at)),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        for expected in keyword.chars() {
+            if self.peek() != Some(expected) {
+                return Err(format!("Expected {} at {}", keyword, self.at));
+            }
+            self.at += 1;
+        }
+
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.at;
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_digit() || "+-.eE".contains(c)) {
+                break;
+            }
+            self.at += 1;
+        }
+
+        let text: String = self.chars[start..self.at].iter().collect();
+        text.parse()
+            .map(Json::Number)
+            .map_err(|_| format!("Invalid number at {}", start))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+
+        let mut s = String::new();
+        loop {
+            let c = self.peek().ok_or("Unterminated string")?;
+            self.at += 1;
+
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escaped = self.peek().ok_or("Unterminated string")?;
+                    self.at += 1;
+                    match escaped {
+                        'n' => s.push('\n'),
+                        'r' => s.push('\r'),
+                        't' => s.push('\t'),
+                        'b' => s.push('\u{8}'),
+                        'f' => s.push('\u{c}'),
+                        'u' => s.push(self.unicode_escape()?),
+                        c => s.push(c),
+                    }
+                }
+                c => s.push(c),
+            }
+        }
+    }
+
+    /// The character of a `\u` escape, combining surrogate pairs.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+        if (0xd800..0xdc00).contains(&high) && self.chars[self.at..].starts_with(&['\\', 'u']) {
+            self.at += 2;
+            let low = self.hex4()?;
+            let code = 0x10000 + ((high - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+            return Ok(char::from_u32(code).unwrap_or('\u{fffd}'));
+        }
+
+        Ok(char::from_u32(high).unwrap_or('\u{fffd}'))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let end = self.at + 4;
+        if end > self.chars.len() {
+            return Err(String::from("Invalid escape"));
+        }
+
+        let digits: String = self.chars[self.at..end].iter().collect();
+        self.at = end;
+        u32::from_str_radix(&digits, 16).map_err(|_| String::from("Invalid escape"))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.at += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.at).copied()
+    }
+
+    /// Skips whitespace and `c` if it comes next.
+    fn next_is(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.at += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.next_is(c) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' at {}", c, self.at))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Json;
+
+    #[test]
+    fn test_parse() {
+        let json =
+            Json::parse(r#" {"id": 1, "method": "a\"bé😀", "params": [true, null, -2.5e1, {}]} "#)
+                .unwrap();
+
+        assert_eq!(Some(1), json.get("id").as_usize());
+        assert_eq!(Some("a\"b\u{e9}\u{1f600}"), json.get("method").as_str());
+        assert_eq!(
+            &[
+                Json::Bool(true),
+                Json::Null,
+                Json::Number(-25.0),
+                Json::Object(vec![])
+            ],
+            json.get("params").as_array().unwrap()
+        );
+        assert!(json.get("missing").is_null());
+
+        assert!(Json::parse("[1,").is_err());
+        assert!(Json::parse("{} x").is_err());
+        assert!(Json::parse(&"[".repeat(1000)).is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let json = Json::object(vec![
+            ("a", Json::from("x\ny")),
+            ("b", Json::from(vec![Json::from(1.5), Json::Null])),
+            ("c", Json::from(false)),
+        ]);
+
+        assert_eq!(r#"{"a":"x\ny","b":[1.5,null],"c":false}"#, json.to_string());
+        assert_eq!(Ok(json.clone()), Json::parse(&json.to_string()));
+    }
+}
@@ -1 +1,3 @@
 pub mod byte_utils;
+pub mod json;
+pub mod rpc;
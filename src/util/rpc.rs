@@ -0,0 +1,113 @@
+//! The base protocol shared by the language server and the debug adapter: JSON messages,
+//! each preceded by a `Content-Length` header and an empty line.
+
+use crate::util::json::Json;
+use std::io;
+use std::io::{BufRead, Write};
+
+// Largest message accepted, so that a bad header cannot make the reader allocate any size.
+const MAX_CONTENT_LENGTH: usize = 64 * 1024 * 1024;
+
+/// Reads the next message, `None` at the end of input. A message that is not valid JSON is
+/// returned as the parse error, a header without a usable `Content-Length` as an error of
+/// kind `InvalidData`.
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Result<Json, String>>> {
+    let mut length = None;
+    let mut headers = 0;
+
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            if headers > 0 {
+                break;
+            }
+            continue;
+        }
+        headers += 1;
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = Some(value.trim().parse::<usize>().map_err(|_| {
+                    invalid_data(format!("Invalid Content-Length '{}'", value.trim()))
+                })?);
+            }
+        }
+    }
+
+    let length = match length {
+        Some(length) if length > MAX_CONTENT_LENGTH => {
+            return Err(invalid_data(format!(
+                "Content-Length of {} bytes exceeds the limit of {}",
+                length, MAX_CONTENT_LENGTH
+            )))
+        }
+        Some(length) => length,
+        None => return Err(invalid_data(String::from("Missing Content-Length header"))),
+    };
+
+    let mut content = vec![0; length];
+    reader.read_exact(&mut content)?;
+
+    Ok(Some(Json::parse(&String::from_utf8_lossy(&content))))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub fn write_message<W: Write>(writer: &mut W, message: &Json) -> io::Result<()> {
+    let content = message.to_string();
+    write!(
+        writer,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_message, write_message};
+    use crate::util::json::Json;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_round_trip() {
+        let mut bytes = Vec::new();
+        let message = Json::object(vec![("text", Json::from("h\u{e9}"))]);
+        write_message(&mut bytes, &message).unwrap();
+        write_message(&mut bytes, &Json::Null).unwrap();
+        bytes.extend_from_slice(b"Content-Length: 3\r\n\r\n{x}");
+
+        let mut reader = Cursor::new(bytes);
+        assert_eq!(Some(Ok(message)), read_message(&mut reader).unwrap());
+        assert_eq!(Some(Ok(Json::Null)), read_message(&mut reader).unwrap());
+        assert!(read_message(&mut reader).unwrap().unwrap().is_err());
+        assert_eq!(None, read_message(&mut reader).unwrap());
+    }
+
+    #[test]
+    fn test_bad_headers() {
+        let read = |bytes: &str| read_message(&mut Cursor::new(bytes.as_bytes().to_vec()));
+
+        let error = read("Content-Type: json\r\n\r\n{}").unwrap_err();
+        assert_eq!("Missing Content-Length header", error.to_string());
+        let error = read("Content-Length: x\r\n\r\n{}").unwrap_err();
+        assert_eq!("Invalid Content-Length 'x'", error.to_string());
+        let error = read("Content-Length: 1000000000000\r\n\r\n{}").unwrap_err();
+        assert_eq!(
+            "Content-Length of 1000000000000 bytes exceeds the limit of 67108864",
+            error.to_string()
+        );
+        assert_eq!(
+            Some(Ok(Json::Null)),
+            read("\r\nContent-Length: 4\r\n\r\nnull").unwrap()
+        );
+    }
+}
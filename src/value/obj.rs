@@ -1,5 +1,4 @@
 use crate::value::obj_str::ObjStr;
-use ustr::Ustr;
 
 #[derive(Debug, Clone)]
 pub enum Obj {
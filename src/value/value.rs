@@ -1,8 +1,6 @@
-use crate::error::error;
 use crate::value::obj::Obj;
 use crate::value::obj_str::ObjStr;
 use std::fmt::{Display, Error, Formatter};
-use ustr::Ustr;
 
 #[derive(Debug, Clone)]
 pub enum Value {
@@ -27,16 +25,24 @@ impl Display for Value {
 
 impl Value {
     pub fn is_falsy(&self) -> bool {
-        let res = match self {
+        match self {
             Value::Nil => true,
             Value::Bool(b) => !*b,
             Value::String(s) => s.string.as_str() == "",
             _ => false,
-        };
+        }
+    }
 
-        res
+    /// Formats the value as the REPL shows it, tagging non-primitive values with their type.
+    pub fn repr(&self) -> String {
+        match self {
+            Value::String(s) => format!("<string> {:?}", s.string.as_str()),
+            Value::Object(_) => format!("<object> {}", self),
+            _ => self.to_string(),
+        }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Value::Bool(a), Value::Bool(b)) => a == b,
@@ -44,7 +50,7 @@ impl Value {
             (Value::Number(a), Value::Number(b)) => a == b,
             (Value::String(l), Value::String(r)) => l.string == r.string,
             (Value::Object(lo), Value::Object(ro)) => lo == ro,
-            _ => true,
+            _ => false,
         }
     }
 }
@@ -68,17 +74,14 @@ impl Value {
                 if p.is_null() {
                     return None;
                 }
-                unsafe { return p.as_mut() }
+                unsafe { p.as_mut() }
             }
             _ => None,
         }
     }
 
     pub fn is_obj(&self) -> bool {
-        match self {
-            Value::Object(_) => true,
-            _ => false,
-        }
+        matches!(self, Value::Object(_))
     }
 }
 
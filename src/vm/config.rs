@@ -0,0 +1,18 @@
+/// Resource limits enforced by the `VM` while it runs a script.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct VMConfig {
+    /// Maximum number of values on the value stack.
+    pub max_stack: usize,
+    /// Maximum number of bytes of strings the script can reach, on the stack or in globals,
+    /// when it creates a string at runtime. Strings it has dropped do not count.
+    pub max_heap: usize,
+}
+
+impl Default for VMConfig {
+    fn default() -> Self {
+        VMConfig {
+            max_stack: 16 * 1024,
+            max_heap: 256 * 1024 * 1024,
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use crate::vm::vm::VM;
+
+/// Observes a VM as it runs, see `VM::interpret_with`. The hook is a type parameter of the
+/// dispatch loop, so running without one, with `NoHook`, costs nothing.
+pub trait DebugHook {
+    /// Called before each instruction, the one at `vm.ip()`. Returning `false` stops the
+    /// execution before it, as an `InterruptHandle` would.
+    fn before_instruction(&mut self, vm: &VM) -> bool;
+}
+
+/// The hook of a VM that is not being debugged.
+pub struct NoHook;
+
+impl DebugHook for NoHook {
+    #[inline(always)]
+    fn before_instruction(&mut self, _: &VM) -> bool {
+        true
+    }
+}
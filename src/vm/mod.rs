@@ -1,2 +1,5 @@
+pub mod config;
+pub mod hook;
+pub mod interrupt;
 pub mod stack;
 pub mod vm;
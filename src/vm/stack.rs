@@ -1,61 +1,110 @@
 use crate::value::value::Value;
 use std::fmt::{Display, Error, Formatter};
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StackError {
+    Overflow,
+    Underflow,
+}
+
+impl Display for StackError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            StackError::Overflow => write!(f, "Stack overflow"),
+            StackError::Underflow => write!(f, "Stack underflow"),
+        }
+    }
+}
+
+pub type StackResult<T> = Result<T, StackError>;
+
 #[derive(Debug)]
 pub struct Stack {
     stack: Vec<Value>,
-    stack_top: usize,
+    max_depth: usize,
 }
 
 impl Stack {
-    pub fn new(capacity: usize) -> Self {
+    pub fn new(capacity: usize, max_depth: usize) -> Self {
         Stack {
-            stack: Vec::with_capacity(capacity),
-            stack_top: 0,
+            stack: Vec::with_capacity(capacity.min(max_depth)),
+            max_depth,
         }
     }
 
     #[inline]
-    pub fn push(&mut self, v: Value) {
+    pub fn push(&mut self, v: Value) -> StackResult<()> {
+        if self.stack.len() >= self.max_depth {
+            return Err(StackError::Overflow);
+        }
+
         self.stack.push(v);
-        self.stack_top += 1
+        Ok(())
     }
 
     #[inline]
-    pub fn pop(&mut self) -> Value {
-        self.stack_top += 1;
-        self.stack.pop().unwrap()
+    pub fn pop(&mut self) -> StackResult<Value> {
+        self.stack.pop().ok_or(StackError::Underflow)
     }
 
     #[inline]
-    pub fn peek(&self) -> Value {
-        self.stack.last().unwrap().clone()
+    pub fn peek(&self) -> StackResult<Value> {
+        self.stack.last().cloned().ok_or(StackError::Underflow)
     }
 
     #[inline]
-    pub fn get(&self, i: usize) -> Value {
-        self.stack.get(i).unwrap().clone()
+    pub fn get(&self, i: usize) -> StackResult<Value> {
+        self.stack.get(i).cloned().ok_or(StackError::Underflow)
     }
 
     #[inline]
-    pub fn set(&mut self, v: Value, i: usize) {
-        self.stack[i] = v;
+    pub fn set(&mut self, v: Value, i: usize) -> StackResult<()> {
+        match self.stack.get_mut(i) {
+            Some(slot) => {
+                *slot = v;
+                Ok(())
+            }
+            None => Err(StackError::Underflow),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// The values from the bottom of the stack to its top.
+    pub fn values(&self) -> &[Value] {
+        &self.stack
     }
 
-    pub fn try_pop(&mut self) -> Option<Value> {
-        self.stack_top += 1;
-        self.stack.pop()
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
     }
 
     pub fn reset(&mut self) {
-        self.stack_top = 0;
+        self.stack.clear();
     }
 }
 
 impl Display for Stack {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        write!(f, "STACK: {:?}", self.stack);
+        write!(f, "STACK: {:?}", self.stack)
+    }
+}
 
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::{Stack, StackError};
+    use crate::value::value::Value;
+
+    #[test]
+    fn test_limits() {
+        let mut stack = Stack::new(1, 2);
+
+        assert_eq!(Err(StackError::Underflow), stack.pop().map(|_| ()));
+        assert_eq!(Ok(()), stack.push(Value::Nil));
+        assert_eq!(Ok(()), stack.push(Value::Nil));
+        assert_eq!(Err(StackError::Overflow), stack.push(Value::Nil));
+        assert_eq!(2, stack.len());
     }
 }
@@ -1,215 +1,670 @@
 use crate::bytecode::chunk::Chunk;
 use crate::bytecode::opcode::OpCode;
-use crate::debug::disassembler::disassemble_instruction;
-use crate::value::value::Value;
-use crate::vm::stack::Stack;
-#[macro_use]
-use crate::debug::debug_print;
+use crate::debug::disassembler::decode_instruction;
 use crate::error::error::Error;
-use crate::value::obj::Obj;
-use crate::value::obj_str::ObjStr;
-use std::collections::HashMap;
+use crate::error::vm::VMError;
+use crate::output::sink::Sink;
+use crate::value::value::Value;
+use crate::vm::config::VMConfig;
+use crate::vm::hook::{DebugHook, NoHook};
+use crate::vm::interrupt::InterruptHandle;
+use crate::vm::stack::{Stack, StackResult};
+use std::cmp::Ordering;
+use std::io::Write;
 use ustr::UstrMap;
 
 const STACK_MAX: usize = 256;
 
-pub type VMRunResult<T> = Result<T, Error>;
+pub type VMRunResult<T> = Result<T, VMError>;
 
 pub struct VM {
     chunk: Option<Chunk>,
     ip: usize,
     stack: Stack,
     globals: UstrMap<Value>,
+    config: VMConfig,
+    heap_size: usize,
+    fuel: Option<u64>,
+    interrupt: InterruptHandle,
+    output: Sink,
+    trace: bool,
 }
 
 impl VM {
     pub fn new() -> Self {
+        VM::with_config(VMConfig::default())
+    }
+
+    pub fn with_config(config: VMConfig) -> Self {
         VM {
             chunk: None,
             ip: 0,
-            stack: Stack::new(STACK_MAX),
+            stack: Stack::new(STACK_MAX, config.max_stack),
             globals: UstrMap::default(),
+            config,
+            heap_size: 0,
+            fuel: None,
+            interrupt: InterruptHandle::new(),
+            output: Sink::stdout(),
+            trace: false,
         }
     }
 
+    /// Redirects the output of `print` statements.
+    pub fn set_output(&mut self, output: Sink) {
+        self.output = output;
+    }
+
+    /// Writes every instruction and the stack to the output before executing it.
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
+    }
+
+    /// Limits execution to `fuel` more instructions, `None` removes the limit.
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel = fuel;
+    }
+
+    pub fn add_fuel(&mut self, fuel: u64) {
+        self.fuel = Some(self.fuel.unwrap_or(0).saturating_add(fuel));
+    }
+
+    pub fn remaining_fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
     pub fn interpret(&mut self, chunk: Chunk) -> VMRunResult<Chunk> {
+        self.interpret_with(chunk, &mut NoHook)
+    }
+
+    /// Runs `chunk` like `interpret`, calling `hook` before every instruction.
+    pub fn interpret_with<H: DebugHook>(
+        &mut self,
+        chunk: Chunk,
+        hook: &mut H,
+    ) -> VMRunResult<Chunk> {
+        chunk.verify().map_err(|e| {
+            VMError::RuntimeError(Error::message(&format!("Invalid bytecode: {}", e)))
+        })?;
+
         self.stack.reset();
         self.ip = 0;
 
         self.chunk = Some(chunk);
-        self.run().map(|o| self.chunk.take().unwrap())
+        self.resume_with(hook)
+    }
+
+    /// Continues an execution that was suspended by running out of fuel or by an interrupt.
+    pub fn resume(&mut self) -> VMRunResult<Chunk> {
+        self.resume_with(&mut NoHook)
+    }
+
+    pub fn resume_with<H: DebugHook>(&mut self, hook: &mut H) -> VMRunResult<Chunk> {
+        if self.chunk.is_none() {
+            return Err(VMError::RuntimeError(Error::message(
+                "No suspended execution to resume",
+            )));
+        }
+
+        match self.run(hook) {
+            Err(e) if e.is_suspension() => Err(e),
+            result => {
+                let chunk = self.chunk.take().unwrap();
+                result.map(|_| chunk)
+            }
+        }
+    }
+
+    /// Drops all globals and any suspended execution.
+    pub fn reset(&mut self) {
+        self.chunk = None;
+        self.ip = 0;
+        self.stack.reset();
+        self.globals.clear();
+        self.heap_size = 0;
+    }
+
+    pub fn globals(&self) -> &UstrMap<Value> {
+        &self.globals
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.chunk.is_some()
+    }
+
+    /// The chunk being run, while it runs or is suspended.
+    pub fn chunk(&self) -> Option<&Chunk> {
+        self.chunk.as_ref()
     }
 
-    fn run(&mut self) -> VMRunResult<()> {
+    /// Offset of the next instruction to run.
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn stack(&self) -> &[Value] {
+        self.stack.values()
+    }
+
+    fn run<H: DebugHook>(&mut self, hook: &mut H) -> VMRunResult<()> {
         loop {
-            disassemble_instruction(self.chunk.as_ref().unwrap(), self.ip);
-            debug!("   ///   {}", &self.stack);
+            if !hook.before_instruction(self) {
+                return Err(VMError::Interrupted);
+            }
+            if let Some(fuel) = self.fuel.as_mut() {
+                if *fuel == 0 {
+                    return Err(VMError::OutOfFuel);
+                }
+                *fuel -= 1;
+            }
+            if self.interrupt.take() {
+                return Err(VMError::Interrupted);
+            }
+
+            if self.trace {
+                self.trace_instruction();
+            }
 
-            let instruction = self.advance_read_instruction();
+            // The chunk was verified before execution, so every opcode byte is valid.
+            let instruction = unsafe { OpCode::from_verified(self.advance_read_instruction()) };
             match instruction {
-                x if x == OpCode::OP_RETURN as u8 => return Ok(()),
-                x if x == OpCode::OP_CONST as u8 => {
+                OpCode::OP_RETURN => return Ok(()),
+                OpCode::OP_CONST => {
                     let constant = self.advance_read_constant();
-                    self.stack.push(constant);
+                    self.push(constant)?;
+                }
+                OpCode::OP_CONST_LONG => {
+                    let constant = self.advance_read_long_constant();
+                    self.push(constant)?;
                 }
-                x if x == OpCode::OP_NEGATE as u8 => {
-                    let res = self.stack.pop();
+                OpCode::OP_NEGATE => {
+                    let res = self.pop()?;
                     let val = match res {
                         Value::Number(v) => -v,
                         _ => return Err(self.runtime_error("Operand must be a number")),
                     };
-                    self.stack.push(Value::Number(val));
+                    self.push(Value::Number(val))?;
                 }
-                x if x == OpCode::OP_ADD as u8 => self.binary_operation("+")?,
-                x if x == OpCode::OP_SUB as u8 => self.binary_operation("-")?,
-                x if x == OpCode::OP_MUL as u8 => self.binary_operation("*")?,
-                x if x == OpCode::OP_DIV as u8 => self.binary_operation("/")?,
-                x if x == OpCode::OP_FALSE as u8 => self.stack.push(Value::Bool(false)),
-                x if x == OpCode::OP_TRUE as u8 => self.stack.push(Value::Bool(true)),
-                x if x == OpCode::OP_NIL as u8 => self.stack.push(Value::Nil),
-                x if x == OpCode::OP_NOT as u8 => {
-                    let val = self.stack.pop();
-                    self.stack.push(Value::Bool(val.is_falsy()))
+                OpCode::OP_ADD => self.binary_operation(OpCode::OP_ADD)?,
+                OpCode::OP_SUB => self.binary_operation(OpCode::OP_SUB)?,
+                OpCode::OP_MUL => self.binary_operation(OpCode::OP_MUL)?,
+                OpCode::OP_DIV => self.binary_operation(OpCode::OP_DIV)?,
+                OpCode::OP_FALSE => self.push(Value::Bool(false))?,
+                OpCode::OP_TRUE => self.push(Value::Bool(true))?,
+                OpCode::OP_NIL => self.push(Value::Nil)?,
+                OpCode::OP_NOT => {
+                    let val = self.pop()?;
+                    self.push(Value::Bool(val.is_falsy()))?
                 }
-                x if x == OpCode::OP_EQUAL as u8 => {
-                    let b = self.stack.pop();
-                    let a = self.stack.pop();
+                OpCode::OP_EQUAL => {
+                    let b = self.pop()?;
+                    let a = self.pop()?;
 
-                    self.stack.push(Value::Bool(a.eq(&b)))
+                    self.push(Value::Bool(a.eq(&b)))?
                 }
-                x if x == OpCode::OP_GREATER as u8 => {
-                    self.binary_operation(">");
+                OpCode::OP_NOT_EQUAL => {
+                    let b = self.pop()?;
+                    let a = self.pop()?;
+
+                    self.push(Value::Bool(!a.eq(&b)))?
                 }
-                x if x == OpCode::OP_LESS as u8 => {
-                    self.binary_operation(">");
+                OpCode::OP_GREATER => self.binary_operation(OpCode::OP_GREATER)?,
+                OpCode::OP_LESS => self.binary_operation(OpCode::OP_LESS)?,
+                OpCode::OP_GREATER_EQUAL => self.binary_operation(OpCode::OP_GREATER_EQUAL)?,
+                OpCode::OP_LESS_EQUAL => self.binary_operation(OpCode::OP_LESS_EQUAL)?,
+                OpCode::OP_PRINT => {
+                    let val = self.pop()?;
+                    if let Err(e) = writeln!(self.output, "{}", val) {
+                        return Err(self.runtime_error(&format!("Unable to print: {}", e)));
+                    }
                 }
-                x if x == OpCode::OP_PRINT as u8 => {
-                    let val = self.stack.pop();
-                    println!("{}", val);
+                OpCode::OP_ECHO => {
+                    let val = self.pop()?;
+                    if let Err(e) = writeln!(self.output, "{}", val.repr()) {
+                        return Err(self.runtime_error(&format!("Unable to print: {}", e)));
+                    }
                 }
-                x if x == OpCode::OP_POP as u8 => {
-                    self.stack.pop();
+                OpCode::OP_POP => {
+                    self.pop()?;
                 }
-                x if x == OpCode::OP_DEF_GLOBAL as u8 => {
+                OpCode::OP_DEF_GLOBAL => {
                     let const_op = self.advance_read_constant();
                     let val = match const_op {
-                        Value::String(s) => s.string.clone(),
-                        _ => {
-                            self.runtime_error("Invalid variable name type.");
-                            continue;
-                        }
+                        Value::String(s) => s.string,
+                        _ => return Err(self.runtime_error("Invalid variable name type.")),
                     };
 
-                    let stack_val = self.stack.pop();
+                    let stack_val = self.pop()?;
 
                     self.globals.insert(val, stack_val);
                 }
-                x if x == OpCode::OP_GET_GLOBAL as u8 => {
+                OpCode::OP_GET_GLOBAL => {
                     let identifier = self.advance_read_constant();
                     let val = match identifier {
-                        Value::String(s) => s.string.clone(),
-                        _ => {
-                            self.runtime_error("Invalid variable name type.");
-                            continue;
-                        }
+                        Value::String(s) => s.string,
+                        _ => return Err(self.runtime_error("Invalid variable name type.")),
                     };
-                    if let Some(v) = self.globals.get(&val) {
-                        self.stack.push(v.clone());
+                    if let Some(v) = self.globals.get(&val).cloned() {
+                        self.push(v)?;
                     } else {
-                        return Err(self.runtime_error(&format!("Undefined variable '{}'.", val.as_str())));
+                        return Err(
+                            self.runtime_error(&format!("Undefined variable '{}'.", val.as_str()))
+                        );
                     }
-                },
-                x if x == OpCode::OP_SET_GLOBAL as u8 => {
+                }
+                OpCode::OP_SET_GLOBAL => {
                     let const_op = self.advance_read_constant();
                     let val = match const_op {
-                        Value::String(s) => s.string.clone(),
-                        _ => {
-                            return Err(self.runtime_error("Invalid variable name type."));
-
-                        }
+                        Value::String(s) => s.string,
+                        _ => return Err(self.runtime_error("Invalid variable name type.")),
                     };
                     if !self.globals.contains_key(&val) {
-                        return Err(self.runtime_error(&format!("Undefined variable '{}'.", val.as_str())));
+                        return Err(
+                            self.runtime_error(&format!("Undefined variable '{}'.", val.as_str()))
+                        );
                     } else {
-                        let stack_val = self.stack.peek();
+                        let stack_val = self.peek()?;
 
                         self.globals.insert(val, stack_val);
                     }
-
-                },
-                x if x == OpCode::OP_GET_LOCAL as u8 => {
+                }
+                OpCode::OP_GET_LOCAL => {
                     let slot = self.advance_read_instruction();
-                    let val = self.stack.get(slot as usize);
-                    self.stack.push(val);
-                },
-                x if x == OpCode::OP_SET_LOCAL as u8 => {
+                    let result = self.stack.get(slot as usize);
+                    let val = self.check_stack(result)?;
+                    self.push(val)?;
+                }
+                OpCode::OP_SET_LOCAL => {
                     let slot = self.advance_read_instruction();
-                    let last_val = self.stack.peek();
-                    self.stack.set(last_val, slot as usize);
+                    let last_val = self.peek()?;
+                    let result = self.stack.set(last_val, slot as usize);
+                    self.check_stack(result)?;
+                }
+                OpCode::OP_ADD_CONST_LOCAL => {
+                    let constant = self.advance_read_constant();
+                    let slot = self.advance_read_instruction() as usize;
+                    let result = self.stack.get(slot);
+                    let local = self.check_stack(result)?;
+                    let sum = self.binary_value(OpCode::OP_ADD, local, constant)?;
+                    let result = self.stack.set(sum, slot);
+                    self.check_stack(result)?;
+                }
+                OpCode::OP_GET_LOCALS => {
+                    for _ in 0..2 {
+                        let slot = self.advance_read_instruction();
+                        let result = self.stack.get(slot as usize);
+                        let val = self.check_stack(result)?;
+                        self.push(val)?;
+                    }
                 }
-                _ => return Err(Error::message("Unknown opcode")),
             }
         }
-
-        Ok(())
     }
 
     #[inline]
     fn advance_read_instruction(&mut self) -> u8 {
-        let instruction = self.chunk.as_ref().unwrap().code.get(self.ip).unwrap();
+        let code = &self.chunk.as_ref().unwrap().code;
+        // Verification guarantees that execution reaches an OP_RETURN before the end of the code.
+        let instruction = unsafe { *code.get_unchecked(self.ip) };
         self.ip += 1;
 
-        *instruction
+        instruction
     }
 
     #[inline]
     fn advance_read_constant(&mut self) -> Value {
         let const_index = self.advance_read_instruction();
-        let constant = self
-            .chunk
-            .as_ref()
-            .unwrap()
-            .const_pool
-            .values
-            .get(const_index as usize)
-            .unwrap();
+        let values = &self.chunk.as_ref().unwrap().const_pool.values;
+
+        // Verification guarantees that constant operands are in bounds of the pool.
+        unsafe { values.get_unchecked(const_index as usize).clone() }
+    }
+
+    #[inline]
+    fn advance_read_long_constant(&mut self) -> Value {
+        let const_index = self.advance_read_instruction() as usize
+            | (self.advance_read_instruction() as usize) << 8
+            | (self.advance_read_instruction() as usize) << 16;
+        let values = &self.chunk.as_ref().unwrap().const_pool.values;
 
-        constant.clone()
+        // Verification guarantees that constant operands are in bounds of the pool.
+        unsafe { values.get_unchecked(const_index).clone() }
     }
 
+    fn trace_instruction(&mut self) {
+        if let Ok(instruction) = decode_instruction(self.chunk.as_ref().unwrap(), self.ip) {
+            let _ = writeln!(
+                self.output,
+                "{:<40} {}",
+                instruction.to_string(),
+                self.stack
+            );
+        }
+    }
+
+    // Errors are raised after the instruction was read, so the last byte read belongs to it.
     fn read_current_executed_line(&mut self) -> usize {
-        self.chunk.as_ref().unwrap().get_code_line(self.ip)
+        self.chunk
+            .as_ref()
+            .unwrap()
+            .get_code_line(self.ip.saturating_sub(1))
+    }
+
+    #[inline]
+    fn binary_operation(&mut self, op: OpCode) -> VMRunResult<()> {
+        let b = self.pop()?;
+        let a = self.pop()?;
+
+        let value = self.binary_value(op, a, b)?;
+        self.push(value)
     }
 
     #[inline]
-    fn binary_operation(&mut self, op: &str) -> VMRunResult<()> {
-        let b = self.stack.pop();
-        let a = self.stack.pop();
-
-        match (op, a, b) {
-            ("+", Value::Number(l), Value::Number(r)) => self.stack.push(Value::Number(l + r)),
-            ("+", Value::String(l), Value::String(r)) => self.stack.push(Value::from(
-                (String::from(l.string.as_str()) + r.string.as_str()).as_str(),
-            )),
-            ("-", Value::Number(l), Value::Number(r)) => self.stack.push(Value::Number(l - r)),
-            ("*", Value::Number(l), Value::Number(r)) => self.stack.push(Value::Number(l * r)),
-            ("*", Value::String(l), Value::Number(r)) => self
-                .stack
-                .push(Value::from((l.string.as_str().repeat(r as usize)).as_str())),
-            ("/", Value::Number(l), Value::Number(r)) => self.stack.push(Value::Number(l / r)),
-            (">", Value::Number(l), Value::Number(r)) => self.stack.push(Value::Bool(l > r)),
-            ("<", Value::Number(l), Value::Number(r)) => self.stack.push(Value::Bool(l < r)),
+    fn binary_value(&mut self, op: OpCode, a: Value, b: Value) -> VMRunResult<Value> {
+        let value = match (op, a, b) {
+            (OpCode::OP_ADD, Value::Number(l), Value::Number(r)) => Value::Number(l + r),
+            (OpCode::OP_ADD, Value::String(l), Value::String(r)) => {
+                let (l, r) = (l.string.as_str(), r.string.as_str());
+                self.allocate(l.len().checked_add(r.len()))?;
+                Value::from((String::from(l) + r).as_str())
+            }
+            (OpCode::OP_SUB, Value::Number(l), Value::Number(r)) => Value::Number(l - r),
+            (OpCode::OP_MUL, Value::Number(l), Value::Number(r)) => Value::Number(l * r),
+            (OpCode::OP_MUL, Value::String(l), Value::Number(r)) => {
+                let l = l.string.as_str();
+                self.allocate(l.len().checked_mul(r as usize))?;
+                Value::from(l.repeat(r as usize).as_str())
+            }
+            (OpCode::OP_DIV, Value::Number(l), Value::Number(r)) => Value::Number(l / r),
+            (OpCode::OP_GREATER, Value::Number(l), Value::Number(r)) => Value::Bool(l > r),
+            (OpCode::OP_LESS, Value::Number(l), Value::Number(r)) => Value::Bool(l < r),
+            // Same as OP_LESS followed by OP_NOT, so a comparison with NaN is true.
+            (OpCode::OP_GREATER_EQUAL, Value::Number(l), Value::Number(r)) => {
+                Value::Bool(l.partial_cmp(&r) != Some(Ordering::Less))
+            }
+            (OpCode::OP_LESS_EQUAL, Value::Number(l), Value::Number(r)) => {
+                Value::Bool(l.partial_cmp(&r) != Some(Ordering::Greater))
+            }
             _ => return Err(self.runtime_error("Invalid binary operator")),
+        };
+
+        Ok(value)
+    }
+
+    #[inline]
+    fn push(&mut self, value: Value) -> VMRunResult<()> {
+        let result = self.stack.push(value);
+        self.check_stack(result)
+    }
+
+    #[inline]
+    fn pop(&mut self) -> VMRunResult<Value> {
+        let result = self.stack.pop();
+        self.check_stack(result)
+    }
+
+    #[inline]
+    fn peek(&mut self) -> VMRunResult<Value> {
+        let result = self.stack.peek();
+        self.check_stack(result)
+    }
+
+    #[inline]
+    fn check_stack<T>(&mut self, result: StackResult<T>) -> VMRunResult<T> {
+        result.map_err(|e| self.runtime_error(&e.to_string()))
+    }
+
+    /// Accounts for `bytes` of newly allocated string data, failing when the heap limit of
+    /// the configuration would be exceeded. `None` stands for a size that overflowed.
+    ///
+    /// Allocations add up until the limit is reached; the strings still reachable are then
+    /// counted again, so those dropped since no longer count against it.
+    fn allocate(&mut self, bytes: Option<usize>) -> VMRunResult<()> {
+        for recount in [false, true] {
+            if recount {
+                self.heap_size = self.live_string_bytes();
+            }
+            match bytes.and_then(|b| self.heap_size.checked_add(b)) {
+                Some(size) if size <= self.config.max_heap => {
+                    self.heap_size = size;
+                    return Ok(());
+                }
+                _ => (),
+            }
         }
 
-        Ok(())
+        Err(self.runtime_error(&format!(
+            "Out of memory: heap limit of {} bytes exceeded",
+            self.config.max_heap
+        )))
+    }
+
+    /// Bytes of the strings on the stack and in globals.
+    fn live_string_bytes(&self) -> usize {
+        self.stack
+            .values()
+            .iter()
+            .chain(self.globals.values())
+            .map(|value| match value {
+                Value::String(s) => s.string.len(),
+                _ => 0,
+            })
+            .sum()
     }
 
-    fn runtime_error(&mut self, message: &str) -> Error {
-        Error::message(&format!(
+    fn runtime_error(&mut self, message: &str) -> VMError {
+        VMError::RuntimeError(Error::message(&format!(
             "{}: {}",
             self.read_current_executed_line(),
             message
-        ))
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VM;
+    use crate::bytecode::chunk::Chunk;
+    use crate::bytecode::opcode::OpCode;
+    use crate::error::vm::VMError;
+    use crate::output::sink::OutputBuffer;
+    use crate::value::value::Value;
+    use crate::vm::config::VMConfig;
+    use crate::vm::hook::{DebugHook, NoHook};
+    use std::thread;
+
+    fn run(chunk: Chunk, config: VMConfig) -> String {
+        match VM::with_config(config).interpret(chunk) {
+            Ok(_) => String::from("ok"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        let mut chunk = Chunk::new();
+        for _ in 0..3 {
+            chunk.write_code(OpCode::OP_NIL, 1);
+        }
+        chunk.write_code(OpCode::OP_RETURN, 1);
+
+        let config = VMConfig {
+            max_stack: 2,
+            ..VMConfig::default()
+        };
+        assert_eq!("1: Stack overflow", run(chunk, config));
+    }
+
+    #[test]
+    fn test_heap_limit() {
+        let mut chunk = Chunk::new();
+        let string = chunk.add_const(Value::from("ab")) as u8;
+        let count = chunk.add_const(Value::Number(1e18)) as u8;
+        chunk.write_code(OpCode::OP_CONST, 1);
+        chunk.write_byte(string, 1);
+        chunk.write_code(OpCode::OP_CONST, 1);
+        chunk.write_byte(count, 1);
+        chunk.write_code(OpCode::OP_MUL, 1);
+        chunk.write_code(OpCode::OP_RETURN, 1);
+
+        let config = VMConfig {
+            max_heap: 1024,
+            ..VMConfig::default()
+        };
+        assert_eq!(
+            "1: Out of memory: heap limit of 1024 bytes exceeded",
+            run(chunk, config)
+        );
+    }
+
+    /// Sets global `s` to "abcd" * 50, then builds and drops `s + s` five times.
+    fn temporaries_chunk() -> Chunk {
+        let mut chunk = Chunk::new();
+        let string = chunk.add_const(Value::from("abcd")) as u8;
+        let count = chunk.add_const(Value::Number(50.0)) as u8;
+        let name = chunk.add_const(Value::from("s")) as u8;
+        chunk.write_code(OpCode::OP_CONST, 1);
+        chunk.write_byte(string, 1);
+        chunk.write_code(OpCode::OP_CONST, 1);
+        chunk.write_byte(count, 1);
+        chunk.write_code(OpCode::OP_MUL, 1);
+        chunk.write_code(OpCode::OP_DEF_GLOBAL, 1);
+        chunk.write_byte(name, 1);
+        for _ in 0..5 {
+            for _ in 0..2 {
+                chunk.write_code(OpCode::OP_GET_GLOBAL, 2);
+                chunk.write_byte(name, 2);
+            }
+            chunk.write_code(OpCode::OP_ADD, 2);
+            chunk.write_code(OpCode::OP_POP, 2);
+        }
+        chunk.write_code(OpCode::OP_RETURN, 2);
+
+        chunk
+    }
+
+    #[test]
+    fn test_heap_limit_counts_live_strings() {
+        // 2200 bytes are allocated in all, but never more than 600 are reachable.
+        let config = VMConfig {
+            max_heap: 600,
+            ..VMConfig::default()
+        };
+        assert_eq!("ok", run(temporaries_chunk(), config));
+
+        let config = VMConfig {
+            max_heap: 599,
+            ..VMConfig::default()
+        };
+        assert_eq!(
+            "2: Out of memory: heap limit of 599 bytes exceeded",
+            run(temporaries_chunk(), config)
+        );
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut chunk = Chunk::new();
+        chunk.write_code(OpCode::OP_NIL, 1);
+        chunk.write_code(OpCode::OP_POP, 1);
+        chunk.write_code(OpCode::OP_RETURN, 2);
+
+        let output = OutputBuffer::new();
+        let mut vm = VM::new();
+        vm.set_output(output.sink());
+        vm.set_trace(true);
+        assert!(vm.interpret(chunk).is_ok());
+
+        let trace = output.take();
+        let lines: Vec<&str> = trace.lines().collect();
+        assert_eq!(3, lines.len());
+        assert!(lines[1].starts_with("0001    1 OP_POP"));
+        assert!(lines[1].ends_with("STACK: [Nil]"));
+        assert!(lines[2].starts_with("0002    2 OP_RETURN"));
+    }
+
+    fn globals_chunk() -> Chunk {
+        let mut chunk = Chunk::new();
+        let name = chunk.add_const(Value::from("a")) as u8;
+        chunk.write_code(OpCode::OP_TRUE, 1);
+        chunk.write_code(OpCode::OP_DEF_GLOBAL, 1);
+        chunk.write_byte(name, 1);
+        chunk.write_code(OpCode::OP_GET_GLOBAL, 2);
+        chunk.write_byte(name, 2);
+        chunk.write_code(OpCode::OP_POP, 2);
+        chunk.write_code(OpCode::OP_RETURN, 2);
+
+        chunk
+    }
+
+    #[test]
+    fn test_fuel_suspends_and_resumes() {
+        let mut vm = VM::new();
+        vm.set_fuel(Some(2));
+
+        match vm.interpret(globals_chunk()) {
+            Err(VMError::OutOfFuel) => (),
+            other => panic!("expected out of fuel, got {:?}", other.map(|_| ())),
+        }
+        assert!(vm.is_suspended());
+        assert_eq!(Some(0), vm.remaining_fuel());
+
+        vm.add_fuel(3);
+        assert!(vm.resume().is_ok());
+        assert!(!vm.is_suspended());
+        assert_eq!(Some(0), vm.remaining_fuel());
+    }
+
+    #[test]
+    fn test_interrupt_from_another_thread() {
+        let mut vm = VM::new();
+        let handle = vm.interrupt_handle();
+        thread::spawn(move || handle.interrupt()).join().unwrap();
+
+        match vm.interpret(globals_chunk()) {
+            Err(VMError::Interrupted) => (),
+            other => panic!("expected interrupt, got {:?}", other.map(|_| ())),
+        }
+        assert!(vm.resume().is_ok());
+    }
+
+    /// Stops once the VM reaches `stop_at`, counting the instructions it saw.
+    struct Breaker {
+        stop_at: usize,
+        seen: usize,
+    }
+
+    impl DebugHook for Breaker {
+        fn before_instruction(&mut self, vm: &VM) -> bool {
+            self.seen += 1;
+            vm.ip() != self.stop_at
+        }
+    }
+
+    #[test]
+    fn test_debug_hook() {
+        let mut vm = VM::new();
+        let mut breaker = Breaker {
+            stop_at: 3,
+            seen: 0,
+        };
+
+        match vm.interpret_with(globals_chunk(), &mut breaker) {
+            Err(VMError::Interrupted) => (),
+            other => panic!("expected interrupt, got {:?}", other.map(|_| ())),
+        }
+        assert_eq!(3, vm.ip());
+        assert_eq!(3, breaker.seen);
+        assert!(vm.stack().is_empty());
+        assert!(vm.globals().contains_key(&ustr::ustr("a")));
+
+        breaker.stop_at = usize::MAX;
+        assert!(vm.resume_with(&mut breaker).is_ok());
+        assert_eq!(6, breaker.seen);
+        assert!(vm.resume_with(&mut NoHook).is_err());
     }
 }
@@ -0,0 +1,62 @@
+//! Replays the sessions recorded under `tests/dap` against `kentauri dap`. In a recording,
+//! `-> ` starts a message of the client and `<- ` one the adapter must answer with, in
+//! order. Programs are launched from paths relative to the crate root.
+
+use kentauri::util::json::Json;
+use kentauri::util::rpc::{read_message, write_message};
+use std::fs;
+use std::io::BufReader;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Replays the recording `name` and returns the exit code of the adapter.
+fn replay(name: &str) -> Option<i32> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let recording = fs::read_to_string(root.join("tests/dap").join(name)).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_kentauri"))
+        .arg("dap")
+        .current_dir(root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    for (i, line) in recording.lines().enumerate() {
+        let parse = |message: &str| Json::parse(message).unwrap();
+
+        if let Some(message) = line.strip_prefix("-> ") {
+            write_message(&mut stdin, &parse(message)).unwrap();
+        } else if let Some(message) = line.strip_prefix("<- ") {
+            let received = read_message(&mut stdout).unwrap();
+            let received = received.map(Result::unwrap);
+            assert_eq!(Some(parse(message)), received, "{} line {}", name, i + 1);
+        }
+    }
+
+    drop(stdin);
+    assert_eq!(
+        None,
+        read_message(&mut stdout).unwrap(),
+        "{} has more",
+        name
+    );
+    child.wait().unwrap().code()
+}
+
+#[test]
+fn test_session() {
+    assert_eq!(Some(0), replay("session.txt"));
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(Some(0), replay("errors.txt"));
+}
+
+#[test]
+fn test_end_of_input_while_stopped() {
+    assert_eq!(Some(1), replay("end_of_input.txt"));
+}
@@ -0,0 +1,8 @@
+var greeting = "hello";
+{
+  var count = 2;
+
+  print greeting;
+  print count;
+}
+print "done";
@@ -0,0 +1,104 @@
+//! Formats every script under `tests/lang` that parses and checks that formatting is
+//! idempotent and that the formatted script prints the same and fails the same way. The
+//! exact lines of errors change with the formatting and are not compared.
+
+use kentauri::interpreter::interpreter::Interpreter;
+use kentauri::output::sink::OutputBuffer;
+use kentauri::syntax::formatter::format;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Guards against scripts that never finish.
+const FUEL: u64 = 10_000_000;
+
+fn scripts(dir: &Path, scripts: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            self::scripts(&path, scripts);
+        } else if path.extension().is_some_and(|ext| ext == "kt") {
+            scripts.push(path);
+        }
+    }
+}
+
+/// What running `source` printed, and the exit code of the error it failed with.
+fn run(source: &str) -> (String, Option<i32>) {
+    let output = OutputBuffer::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(output.sink());
+    interpreter.set_diagnostics(OutputBuffer::new().sink());
+    interpreter.set_fuel(Some(FUEL));
+
+    let error = interpreter.interpret(source).err().map(|e| e.exit_code());
+    (output.take(), error)
+}
+
+#[test]
+fn test_round_trip() {
+    let mut paths = Vec::new();
+    scripts(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lang"),
+        &mut paths,
+    );
+    paths.sort();
+
+    let mut formatted_scripts = 0;
+    for path in paths {
+        let source = fs::read_to_string(&path).unwrap();
+        let formatted = match format(&source) {
+            Ok(formatted) => formatted,
+            Err(_) => continue,
+        };
+        formatted_scripts += 1;
+
+        assert_eq!(
+            Some(&formatted),
+            format(&formatted).ok().as_ref(),
+            "{} is not formatted idempotently",
+            path.display()
+        );
+
+        let (output, error) = run(&source);
+        let (formatted_output, formatted_error) = run(&formatted);
+        assert_eq!(
+            output,
+            formatted_output,
+            "{} prints differently",
+            path.display()
+        );
+        assert_eq!(
+            error,
+            formatted_error,
+            "{} fails differently",
+            path.display()
+        );
+    }
+
+    assert!(formatted_scripts > 0);
+}
+
+#[test]
+fn test_keeps_comments() {
+    for path in ["comments/line_at_eof.kt", "comments/unicode.kt"].iter() {
+        let source = fs::read_to_string(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/lang")
+                .join(path),
+        )
+        .unwrap();
+        let formatted = format(&source).unwrap();
+
+        for line in source
+            .lines()
+            .filter_map(|line| line.find("//").map(|i| &line[i..]))
+        {
+            assert!(
+                formatted.contains(line.trim_end()),
+                "{} lost {:?}",
+                path,
+                line
+            );
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Runs the fuzz targets of `kentauri::fuzz` on the scripts under `tests/lang`, mutations of
+//! them and generated programs. `KENTAURI_FUZZ_ITERATIONS` sets how many inputs are
+//! generated. Runs use a fixed seed unless `KENTAURI_FUZZ_SEED` sets one, or is `random`
+//! for a new seed each time.
+
+use kentauri::fuzz;
+use kentauri::fuzz::generator::Generator;
+use std::env;
+use std::fs;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const ITERATIONS: usize = 500;
+const SEED: u64 = 0x6b656e74;
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+fn seeds() -> Vec<Vec<u8>> {
+    let mut seeds = Vec::new();
+    let mut dirs = vec![Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lang")];
+
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "kt") {
+                seeds.push(fs::read(path).unwrap());
+            }
+        }
+    }
+
+    seeds.sort();
+    seeds
+}
+
+/// Feeds `data` to every target, reporting the input that broke one.
+fn check(data: &[u8]) {
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        fuzz::scan(data);
+        if let Some(chunk) = fuzz::compile(data) {
+            let _ = fuzz::run(chunk);
+        }
+        fuzz::optimize(data);
+        fuzz::parse(data);
+        fuzz::format(data);
+        fuzz::execute(data);
+    }));
+
+    if result.is_err() {
+        panic!("input failed: {:?}", String::from_utf8_lossy(data));
+    }
+}
+
+#[test]
+fn test_seeds() {
+    for seed in seeds() {
+        check(&seed);
+    }
+}
+
+#[test]
+fn test_generated() {
+    let iterations = env_or("KENTAURI_FUZZ_ITERATIONS", ITERATIONS);
+    let seed = match env::var("KENTAURI_FUZZ_SEED").as_deref() {
+        Ok("random") => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        _ => env_or("KENTAURI_FUZZ_SEED", SEED),
+    };
+    println!("KENTAURI_FUZZ_SEED={}", seed);
+
+    let seeds = seeds();
+    let mut generator = Generator::new(seed);
+    for i in 0..iterations {
+        check(generator.program().as_bytes());
+        check(&generator.mutate(&seeds[i % seeds.len()]));
+        check(&generator.bytes(64));
+    }
+}
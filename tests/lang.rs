@@ -0,0 +1,227 @@
+//! Runs every script under `tests/lang` and compares what it does with the expectations
+//! written in its comments:
+//!
+//! - `// expect: <text>` is a line the script prints, in order.
+//! - `// expect runtime error: <message>` is the runtime error the script stops with, raised
+//!   on the line of the comment.
+//! - `// [line N] Error...` is a compile error exactly as the compiler reports it, and
+//!   `// Error...` is short for one on the line of the comment.
+//!
+//! `cargo test --test lang -- <filter>` only runs the scripts whose path contains `filter`,
+//! and `cargo test --test lang -- --update` rewrites the expectations of failing scripts
+//! from what they actually do.
+
+use kentauri::error::interpreter::InterpreterError;
+use kentauri::interpreter::interpreter::Interpreter;
+use kentauri::output::sink::OutputBuffer;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+const EXPECT: &str = "// expect: ";
+const EXPECT_RUNTIME_ERROR: &str = "// expect runtime error: ";
+
+// Guards against scripts that never finish.
+const FUEL: u64 = 10_000_000;
+
+#[derive(Debug, Default, PartialEq)]
+struct Outcome {
+    output: Vec<String>,
+    /// Line and message of the runtime error the script stopped with.
+    runtime_error: Option<(usize, String)>,
+    compile_errors: Vec<String>,
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let update = args.iter().any(|arg| arg == "--update");
+    let filters: Vec<&String> = args.iter().filter(|arg| !arg.starts_with('-')).collect();
+
+    let mut scripts = Vec::new();
+    collect_scripts(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lang"),
+        &mut scripts,
+    );
+    scripts.retain(|path| {
+        filters.is_empty()
+            || filters
+                .iter()
+                .any(|filter| path.to_string_lossy().contains(filter.as_str()))
+    });
+
+    let mut failed = 0;
+    for path in scripts.iter() {
+        let source = fs::read_to_string(path).unwrap();
+        let expected = expectations(&source);
+        let actual = run(&source);
+
+        if expected == actual {
+            continue;
+        }
+
+        if update {
+            fs::write(path, update_expectations(&source, &actual)).unwrap();
+            println!("updated {}", path.display());
+            continue;
+        }
+
+        failed += 1;
+        println!("FAIL {}", path.display());
+        report(&expected, &actual);
+    }
+
+    println!(
+        "lang: {} scripts, {} passed, {} failed",
+        scripts.len(),
+        scripts.len() - failed,
+        failed
+    );
+    if failed > 0 {
+        exit(1);
+    }
+}
+
+fn collect_scripts(dir: &Path, scripts: &mut Vec<PathBuf>) {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            collect_scripts(&path, scripts);
+        } else if path.extension().is_some_and(|ext| ext == "kt") {
+            scripts.push(path);
+        }
+    }
+}
+
+fn run(source: &str) -> Outcome {
+    let output = OutputBuffer::new();
+    let diagnostics = OutputBuffer::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(output.sink());
+    interpreter.set_diagnostics(diagnostics.sink());
+    interpreter.set_fuel(Some(FUEL));
+
+    let runtime_error = match interpreter.interpret(source) {
+        Err(InterpreterError::RuntimeError(e)) => {
+            let message = e.to_string();
+            let (line, message) = message.split_at(message.find(": ").unwrap());
+            Some((line.parse().unwrap(), message[2..].to_string()))
+        }
+        Err(e @ InterpreterError::OutOfFuel) => Some((0, e.to_string())),
+        _ => None,
+    };
+
+    Outcome {
+        output: output.take().lines().map(String::from).collect(),
+        runtime_error,
+        compile_errors: diagnostics.take().lines().map(String::from).collect(),
+    }
+}
+
+fn expectations(source: &str) -> Outcome {
+    let mut expected = Outcome::default();
+
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+
+        if let Some(index) = line.find(EXPECT) {
+            expected
+                .output
+                .push(line[index + EXPECT.len()..].to_string());
+        } else if let Some(index) = line.find(EXPECT_RUNTIME_ERROR) {
+            let message = line[index + EXPECT_RUNTIME_ERROR.len()..].to_string();
+            expected.runtime_error = Some((line_number, message));
+        } else if let Some(index) = line.find("// [line ") {
+            expected.compile_errors.push(line[index + 3..].to_string());
+        } else if let Some(index) = line.find("// Error") {
+            expected
+                .compile_errors
+                .push(format!("[line {}] {}", line_number, &line[index + 3..]));
+        }
+    }
+
+    expected
+}
+
+fn report(expected: &Outcome, actual: &Outcome) {
+    let lines = expected.output.len().max(actual.output.len());
+    for i in 0..lines {
+        let (e, a) = (expected.output.get(i), actual.output.get(i));
+        if e != a {
+            println!("  output line {}: expected {:?}, got {:?}", i + 1, e, a);
+        }
+    }
+
+    if expected.runtime_error != actual.runtime_error {
+        println!(
+            "  runtime error: expected {:?}, got {:?}",
+            expected.runtime_error, actual.runtime_error
+        );
+    }
+
+    for missing in expected
+        .compile_errors
+        .iter()
+        .filter(|e| !actual.compile_errors.contains(e))
+    {
+        println!("  missing compile error: {}", missing);
+    }
+    for unexpected in actual
+        .compile_errors
+        .iter()
+        .filter(|e| !expected.compile_errors.contains(e))
+    {
+        println!("  unexpected compile error: {}", unexpected);
+    }
+}
+
+/// Rewrites the expectation comments of `source` to match `actual`. Output expectations
+/// are replaced in order and compile errors are listed at the end of the script, so no
+/// line moves and the line numbers in the expectations stay valid.
+fn update_expectations(source: &str, actual: &Outcome) -> String {
+    let markers = [EXPECT, EXPECT_RUNTIME_ERROR, "// [line ", "// Error"];
+    let mut outputs = actual.output.iter();
+    let mut lines: Vec<String> = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let mut code = match markers.iter().filter_map(|m| line.find(m)).min() {
+            Some(index) => line[..index].trim_end().to_string(),
+            None => line.to_string(),
+        };
+
+        if line.contains(EXPECT) {
+            if let Some(output) = outputs.next() {
+                code = join_comment(&code, &format!("{}{}", EXPECT, output));
+            }
+        }
+
+        if let Some((error_line, message)) = &actual.runtime_error {
+            if *error_line == i + 1 {
+                code = join_comment(&code, &format!("{}{}", EXPECT_RUNTIME_ERROR, message));
+            }
+        }
+
+        lines.push(code);
+    }
+
+    for output in outputs {
+        lines.push(format!("{}{}", EXPECT, output));
+    }
+    for error in actual.compile_errors.iter() {
+        lines.push(format!("// {}", error));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+fn join_comment(code: &str, comment: &str) -> String {
+    if code.is_empty() {
+        comment.to_string()
+    } else {
+        format!("{} {}", code, comment)
+    }
+}
@@ -0,0 +1,9 @@
+var a = "a";
+var b = "b";
+var c = "c";
+
+// Assignment is right-associative.
+a = b = c;
+print a; // expect: c
+print b; // expect: c
+print c; // expect: c
@@ -0,0 +1,8 @@
+var a = "before";
+print a; // expect: before
+
+a = "after";
+print a; // expect: after
+
+print a = "arg"; // expect: arg
+print a; // expect: arg
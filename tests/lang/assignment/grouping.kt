@@ -0,0 +1,2 @@
+var a = "a";
+(a) = "value"; // Error at '=': Invalid assignment target.
@@ -0,0 +1,10 @@
+{
+  var a = "before";
+  print a; // expect: before
+
+  a = "after";
+  print a; // expect: after
+
+  print a = "arg"; // expect: arg
+  print a; // expect: arg
+}
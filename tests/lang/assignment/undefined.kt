@@ -0,0 +1 @@
+unknown = "what"; // expect runtime error: Undefined variable 'unknown'.
@@ -0,0 +1,15 @@
+print true == true;    // expect: true
+print true == false;   // expect: false
+print false == true;   // expect: false
+print false == false;  // expect: true
+
+// Not equal to other types.
+print true == 1;        // expect: false
+print false == 0;       // expect: false
+print true == "true";   // expect: false
+print false == "false"; // expect: false
+print false == nil;     // expect: false
+
+print true != true;    // expect: false
+print true != false;   // expect: true
+print false != nil;    // expect: true
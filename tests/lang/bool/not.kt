@@ -0,0 +1,9 @@
+print !true;    // expect: false
+print !false;   // expect: true
+print !!true;   // expect: true
+print !nil;     // expect: true
+print !0;       // expect: false
+print !"s";     // expect: false
+
+// The empty string is falsy.
+print !"";      // expect: true
@@ -0,0 +1,2 @@
+print "ok"; // expect: ok
+// comment
@@ -0,0 +1,7 @@
+// Unicode characters are allowed in comments.
+//
+// Latin 1 Supplement: £§¶ÜÞ
+// Latin Extended-A: ĐĦŋœ
+// Emoji: ☃☺♣
+
+print "ok"; // expect: ok
@@ -0,0 +1 @@
+print 1 < "1"; // expect runtime error: Invalid binary operator
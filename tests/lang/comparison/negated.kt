@@ -0,0 +1,10 @@
+// The negated comparisons behave as the negation of the opposite one.
+var nan = 0 / 0;
+var one = 1;
+print nan >= one; // expect: true
+print nan <= one; // expect: true
+print one >= one; // expect: true
+print one <= 0; // expect: false
+print one != "1"; // expect: true
+print one != one; // expect: false
+print one >= "1"; // expect runtime error: Invalid binary operator
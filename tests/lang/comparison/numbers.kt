@@ -0,0 +1,20 @@
+print 1 < 2;    // expect: true
+print 2 < 2;    // expect: false
+print 2 < 1;    // expect: false
+
+print 1 <= 2;    // expect: true
+print 2 <= 2;    // expect: true
+print 2 <= 1;    // expect: false
+
+print 1 > 2;    // expect: false
+print 2 > 2;    // expect: false
+print 2 > 1;    // expect: true
+
+print 1 >= 2;    // expect: false
+print 2 >= 2;    // expect: true
+print 2 >= 1;    // expect: true
+
+// Zero and negative zero compare the same.
+print 0 < -0; // expect: false
+print -0 < 0; // expect: false
+print 0 >= -0; // expect: true
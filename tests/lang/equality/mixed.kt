@@ -0,0 +1,11 @@
+print nil == nil; // expect: true
+print nil == 0;   // expect: false
+print 1 == 1;     // expect: true
+print 1 == 2;     // expect: false
+print 1 == "1";   // expect: false
+print "str" == "str"; // expect: true
+print "str" == "ing"; // expect: false
+print "a" + "b" == "ab"; // expect: true
+
+print nil != nil; // expect: false
+print 1 != "1";   // expect: true
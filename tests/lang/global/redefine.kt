@@ -0,0 +1,3 @@
+var a = "1";
+var a = "2";
+print a; // expect: 2
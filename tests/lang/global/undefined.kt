@@ -0,0 +1,3 @@
+print "before"; // expect: before
+print notDefined; // expect runtime error: Undefined variable 'notDefined'.
+print "after";
@@ -0,0 +1,3 @@
+var a = "value";
+var a = a;
+print a; // expect: value
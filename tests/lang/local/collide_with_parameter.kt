@@ -0,0 +1,4 @@
+{
+  var a = "value";
+  var a = "other"; // Error at 'a': Variable with name 'a' already declared in this scope
+}
@@ -0,0 +1,6 @@
+var a = "global";
+{
+  var b = "local";
+  print a + b; // expect: globallocal
+}
+print b; // expect runtime error: Undefined variable 'b'.
@@ -0,0 +1,6 @@
+var a = "global";
+{
+  var a = "shadow";
+  print a; // expect: shadow
+}
+print a; // expect: global
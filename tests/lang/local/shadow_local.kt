@@ -0,0 +1,8 @@
+{
+  var a = "local";
+  {
+    var a = "shadow";
+    print a; // expect: shadow
+  }
+  print a; // expect: local
+}
@@ -0,0 +1,4 @@
+var a = "outer";
+{
+  var a = a; // Error at 'a': Cannot read local variable 'a' in its own initializer
+}
@@ -0,0 +1 @@
+print nil; // expect: nil
@@ -0,0 +1,2 @@
+// A trailing '.' is not part of the number.
+print 123. // Error at '.': Expect ';' after value.
@@ -0,0 +1,6 @@
+print 123;     // expect: 123
+print 987654;  // expect: 987654
+print 0;       // expect: 0
+print -0;      // expect: -0
+print 123.456; // expect: 123.456
+print -0.001;  // expect: -0.001
@@ -0,0 +1 @@
+print 1 + "s"; // expect runtime error: Invalid binary operator
@@ -0,0 +1,11 @@
+print 123 + 456; // expect: 579
+print 4 - 3;     // expect: 1
+print 1.2 - 1.2; // expect: 0
+print 5 * 3;     // expect: 15
+print 12.34 * 0.3; // expect: 3.702
+print 8 / 2;     // expect: 4
+print 12.34 / 12.34; // expect: 1
+print -(3);      // expect: -3
+print --3;       // expect: 3
+print 1 / 0;     // expect: inf
+print 0.1 + 0.2; // expect: 0.30000000000000004
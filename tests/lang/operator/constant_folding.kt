@@ -0,0 +1,16 @@
+// Operators on literals are evaluated by the compiler and must behave as at runtime.
+print 1 + 2 * 3; // expect: 7
+print (1 + 2) * 3; // expect: 9
+print -5; // expect: -5
+print -(1 - 1); // expect: -0
+print !true; // expect: false
+print !""; // expect: true
+print "a" + "b" + "c"; // expect: abc
+print 1 / 0; // expect: inf
+print 0.1 + 0.2; // expect: 0.30000000000000004
+print 0 / 0 == 0 / 0; // expect: false
+print 0 / 0 >= 1; // expect: true
+print 1 <= 2; // expect: true
+print "1" == 1; // expect: false
+print nil != false; // expect: true
+print "a" - 1; // expect runtime error: Invalid binary operator
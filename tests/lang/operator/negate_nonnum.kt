@@ -0,0 +1 @@
+print -"s"; // expect runtime error: Operand must be a number
@@ -0,0 +1,4 @@
+var a = "s";
+print 1;  // expect: 1
+print
+  -a; // expect runtime error: Operand must be a number
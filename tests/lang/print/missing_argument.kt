@@ -0,0 +1 @@
+print; // Error at ';': Expect expression
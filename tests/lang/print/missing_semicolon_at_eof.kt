@@ -0,0 +1,3 @@
+// The end of the input is on the line after the final newline.
+// [line 4] Error at end: Expect ';' after value.
+print 1
@@ -0,0 +1,6 @@
+var andy = 1;
+var formless = 2;
+var fo = 3;
+var _under = 4;
+var camelCase2 = 5;
+print andy + formless + fo + _under + camelCase2; // expect: 15
@@ -0,0 +1,5 @@
+print "(" + "" + ")";   // expect: ()
+print "a string"; // expect: a string
+
+// Non-ASCII.
+print "A~¶Þॐஃ"; // expect: A~¶Þॐஃ
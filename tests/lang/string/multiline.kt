@@ -0,0 +1,7 @@
+var a = "1
+2
+3";
+print a;
+// expect: 1
+// expect: 2
+// expect: 3
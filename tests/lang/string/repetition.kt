@@ -0,0 +1,3 @@
+print "ab" * 3; // expect: ababab
+print "ab" * 0; // expect: 
+print "x" + "y" * 2; // expect: xyy
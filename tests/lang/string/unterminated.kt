@@ -0,0 +1,3 @@
+// The error is reported at the end of the input.
+// [line 4] Error: Unterminated string
+"this string has no close quote
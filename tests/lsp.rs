@@ -0,0 +1,251 @@
+//! Drives `kentauri lsp` as an editor would, through a whole session over its stdin and
+//! stdout.
+
+use kentauri::util::json::Json;
+use kentauri::util::rpc::{read_message, write_message};
+use std::io::{BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+const URI: &str = "file:///test.kt";
+
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: usize,
+}
+
+impl Client {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_kentauri"))
+            .arg("lsp")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        Client {
+            stdin: child.stdin.take().unwrap(),
+            stdout: BufReader::new(child.stdout.take().unwrap()),
+            child,
+            next_id: 1,
+        }
+    }
+
+    fn send(&mut self, message: Json) {
+        write_message(&mut self.stdin, &message).unwrap();
+    }
+
+    fn receive(&mut self) -> Json {
+        read_message(&mut self.stdout).unwrap().unwrap().unwrap()
+    }
+
+    fn notify(&mut self, method: &str, params: Json) {
+        self.send(Json::object(vec![
+            ("jsonrpc", Json::from("2.0")),
+            ("method", Json::from(method)),
+            ("params", params),
+        ]));
+    }
+
+    /// Sends a request and returns its response.
+    fn request(&mut self, method: &str, params: Json) -> Json {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(Json::object(vec![
+            ("jsonrpc", Json::from("2.0")),
+            ("id", Json::from(id)),
+            ("method", Json::from(method)),
+            ("params", params),
+        ]));
+
+        let response = self.receive();
+        assert_eq!(Some(id), response.get("id").as_usize());
+        response
+    }
+
+    /// Sends a request about a position of the test document.
+    fn request_at(&mut self, method: &str, line: usize, character: usize) -> Json {
+        self.request(
+            method,
+            Json::object(vec![
+                ("textDocument", document()),
+                (
+                    "position",
+                    Json::object(vec![
+                        ("line", Json::from(line)),
+                        ("character", Json::from(character)),
+                    ]),
+                ),
+            ]),
+        )
+    }
+
+    /// Opens or changes the test document and returns the diagnostics published for it.
+    fn edit(&mut self, method: &str, text: &str) -> Vec<Json> {
+        let params = match method {
+            "textDocument/didOpen" => Json::object(vec![(
+                "textDocument",
+                Json::object(vec![
+                    ("uri", Json::from(URI)),
+                    ("languageId", Json::from("kentauri")),
+                    ("version", Json::from(1)),
+                    ("text", Json::from(text)),
+                ]),
+            )]),
+            _ => Json::object(vec![
+                ("textDocument", document()),
+                (
+                    "contentChanges",
+                    Json::from(vec![Json::object(vec![("text", Json::from(text))])]),
+                ),
+            ]),
+        };
+        self.notify(method, params);
+
+        let notification = self.receive();
+        assert_eq!(
+            Some("textDocument/publishDiagnostics"),
+            notification.get("method").as_str()
+        );
+        assert_eq!(Some(URI), notification.get("params").get("uri").as_str());
+        notification
+            .get("params")
+            .get("diagnostics")
+            .as_array()
+            .unwrap()
+            .to_vec()
+    }
+}
+
+fn document() -> Json {
+    Json::object(vec![("uri", Json::from(URI))])
+}
+
+fn start(range: &Json) -> (usize, usize) {
+    let start = range.get("start");
+    (
+        start.get("line").as_usize().unwrap(),
+        start.get("character").as_usize().unwrap(),
+    )
+}
+
+#[test]
+fn test_session() {
+    let mut client = Client::start();
+
+    let initialize = client.request("initialize", Json::object(vec![]));
+    let capabilities = initialize.get("result").get("capabilities");
+    assert_eq!(Some(1), capabilities.get("textDocumentSync").as_usize());
+    assert!(capabilities.get("definitionProvider").as_bool().unwrap());
+    client.notify("initialized", Json::object(vec![]));
+
+    // A syntax error, then a name error, then a warning once it compiles.
+    let diagnostics = client.edit("textDocument/didOpen", "var a = 1;\nprint a +;\n");
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(
+        Some("Expect expression"),
+        diagnostics[0].get("message").as_str()
+    );
+    assert_eq!(Some(1), diagnostics[0].get("severity").as_usize());
+    assert_eq!((1, 9), start(diagnostics[0].get("range")));
+
+    let diagnostics = client.edit("textDocument/didChange", "{ var a = 1; var a = 2; }");
+    assert_eq!(1, diagnostics.len());
+    assert_eq!((0, 17), start(diagnostics[0].get("range")));
+
+    let source = "var a = \"\u{e9}\";\n{\n  var b = a;\n  print b;\n}\na  =  a;\n{ var c; }\n";
+    let diagnostics = client.edit("textDocument/didChange", source);
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(Some(2), diagnostics[0].get("severity").as_usize());
+    assert_eq!((6, 6), start(diagnostics[0].get("range")));
+
+    let tokens = client.request(
+        "textDocument/semanticTokens/full",
+        Json::object(vec![("textDocument", document())]),
+    );
+    let data = tokens.get("result").get("data").as_array().unwrap();
+    let data: Vec<usize> = data.iter().map(|n| n.as_usize().unwrap()).collect();
+    assert_eq!(vec![0, 0, 3, 0, 0, 0, 4, 1, 1, 0], data[..10].to_vec());
+
+    let definition = client.request_at("textDocument/definition", 2, 10);
+    let location = definition.get("result");
+    assert_eq!(Some(URI), location.get("uri").as_str());
+    assert_eq!((0, 4), start(location.get("range")));
+
+    let definition = client.request_at("textDocument/definition", 3, 8);
+    assert_eq!((2, 6), start(definition.get("result").get("range")));
+
+    let hover = client.request_at("textDocument/hover", 3, 9);
+    let contents = hover.get("result").get("contents").get("value");
+    assert_eq!(
+        Some("```kentauri\nvar b = a;\n```\nLocal variable declared on line 3"),
+        contents.as_str()
+    );
+    assert!(client
+        .request_at("textDocument/hover", 1, 0)
+        .get("result")
+        .is_null());
+
+    let symbols = client.request(
+        "textDocument/documentSymbol",
+        Json::object(vec![("textDocument", document())]),
+    );
+    let names: Vec<&str> = symbols
+        .get("result")
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| symbol.get("name").as_str().unwrap())
+        .collect();
+    assert_eq!(vec!["a", "b", "c"], names);
+
+    let formatting = client.request(
+        "textDocument/formatting",
+        Json::object(vec![("textDocument", document())]),
+    );
+    let edits = formatting.get("result").as_array().unwrap();
+    assert_eq!(1, edits.len());
+    assert_eq!(
+        Some("var a = \"\u{e9}\";\n{\n    var b = a;\n    print b;\n}\na = a;\n{\n    var c;\n}\n"),
+        edits[0].get("newText").as_str()
+    );
+
+    let unknown = client.request("textDocument/rename", Json::object(vec![]));
+    assert_eq!(Some(-32601.0), unknown.get("error").get("code").as_f64());
+
+    client.notify(
+        "textDocument/didClose",
+        Json::object(vec![("textDocument", document())]),
+    );
+    let cleared = client.receive();
+    assert_eq!(
+        Some(0),
+        cleared
+            .get("params")
+            .get("diagnostics")
+            .as_array()
+            .map(<[Json]>::len)
+    );
+
+    client.request("shutdown", Json::Null);
+    client.notify("exit", Json::Null);
+    client.stdin.flush().unwrap();
+    assert_eq!(Some(0), client.child.wait().unwrap().code());
+}
+
+#[test]
+fn test_exit_without_shutdown() {
+    let mut client = Client::start();
+
+    client
+        .stdin
+        .write_all(b"Content-Length: 5\r\n\r\n{oops")
+        .unwrap();
+    let response = client.receive();
+    assert_eq!(Some(-32700.0), response.get("error").get("code").as_f64());
+    assert!(response.get("id").is_null());
+
+    client.notify("exit", Json::Null);
+    assert_eq!(Some(1), client.child.wait().unwrap().code());
+}